@@ -0,0 +1,252 @@
+use std::{error::Error, fmt::Display, num::ParseIntError};
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    Instruction(String),
+    Register(String),
+    Value(ParseIntError),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Instruction(line) => write!(f, "invalid instruction: {:?}", line),
+            ParseError::Register(reg) => write!(f, "invalid register: {:?}", reg),
+            ParseError::Value(err) => write!(f, "invalid value: {}", err),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// One of the 26 registers `a` to `z`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Register(u8);
+
+impl Register {
+    pub const X: Register = Register(b'x' - b'a');
+
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        match input.as_bytes() {
+            [c @ b'a'..=b'z'] => Ok(Register(c - b'a')),
+            _ => Err(ParseError::Register(input.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Instruction {
+    Noop,
+    Add(Register, i32),
+    Jump(isize),
+    JumpNotZero(Register, isize),
+}
+
+impl Instruction {
+    /// Understands `noop`, `add<reg> <val>`, `jmp <offset>` and `jnz <reg> <offset>`.
+    pub fn parse(line: &str) -> Result<Self, ParseError> {
+        let mut words = line.split(' ');
+        let instruction = match (words.next(), words.next(), words.next()) {
+            (Some("noop"), None, None) => Instruction::Noop,
+            (Some("jmp"), Some(offset), None) => {
+                Instruction::Jump(offset.parse().map_err(ParseError::Value)?)
+            }
+            (Some("jnz"), Some(reg), Some(offset)) => Instruction::JumpNotZero(
+                Register::parse(reg)?,
+                offset.parse().map_err(ParseError::Value)?,
+            ),
+            (Some(op), Some(val), None) if op.starts_with("add") => Instruction::Add(
+                Register::parse(&op[3..])?,
+                val.parse().map_err(ParseError::Value)?,
+            ),
+            _ => return Err(ParseError::Instruction(line.to_string())),
+        };
+
+        Ok(instruction)
+    }
+
+    fn cycles(&self) -> usize {
+        match self {
+            Instruction::Add(_, _) => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// Gets called once per cycle, seeing the registers as they are during that cycle.
+pub trait Observer {
+    fn observe(&mut self, cycle: usize, cpu: &Cpu);
+}
+
+pub struct Cpu {
+    registers: [i32; 26],
+    pc: usize,
+    cycle: usize,
+}
+
+impl Default for Cpu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Cpu {
+    /// Registers start at zero, except for `x` which starts at one.
+    pub fn new() -> Self {
+        let mut registers = [0; 26];
+        registers[Register::X.0 as usize] = 1;
+
+        Cpu {
+            registers,
+            pc: 0,
+            cycle: 0,
+        }
+    }
+
+    pub fn get(&self, register: Register) -> i32 {
+        self.registers[register.0 as usize]
+    }
+
+    pub fn get_cycle(&self) -> usize {
+        self.cycle
+    }
+
+    /// Executes the instruction under the program counter, returns false once it runs off the program.
+    pub fn step(&mut self, program: &[Instruction], observers: &mut [&mut dyn Observer]) -> bool {
+        let Some(instruction) = program.get(self.pc) else {
+            return false;
+        };
+
+        for _ in 0..instruction.cycles() {
+            self.cycle += 1;
+            observers
+                .iter_mut()
+                .for_each(|observer| observer.observe(self.cycle, self));
+        }
+
+        let offset = match instruction {
+            Instruction::Noop => 1,
+            Instruction::Add(reg, val) => {
+                self.registers[reg.0 as usize] += val;
+                1
+            }
+            Instruction::Jump(offset) => *offset,
+            Instruction::JumpNotZero(reg, offset) if self.get(*reg) != 0 => *offset,
+            Instruction::JumpNotZero(_, _) => 1,
+        };
+
+        match self.pc.checked_add_signed(offset) {
+            Some(pc) => {
+                self.pc = pc;
+                pc < program.len()
+            }
+            None => {
+                self.pc = program.len();
+                false
+            }
+        }
+    }
+
+    /// Runs until the program counter leaves the program, which may be never if it loops.
+    pub fn run(&mut self, program: &[Instruction], observers: &mut [&mut dyn Observer]) {
+        while self.step(program, observers) {}
+    }
+}
+
+pub struct SignalSampler {
+    points: Vec<usize>,
+    sum: i32,
+}
+
+impl SignalSampler {
+    pub fn new(points: impl IntoIterator<Item = usize>) -> Self {
+        SignalSampler {
+            points: points.into_iter().collect(),
+            sum: 0,
+        }
+    }
+
+    pub fn get_sum(&self) -> i32 {
+        self.sum
+    }
+}
+
+impl Observer for SignalSampler {
+    fn observe(&mut self, cycle: usize, cpu: &Cpu) {
+        if self.points.contains(&cycle) {
+            self.sum += cycle as i32 * cpu.get(Register::X);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_instructions() {
+        assert_eq!(Instruction::parse("noop"), Ok(Instruction::Noop));
+        assert_eq!(
+            Instruction::parse("addx -5"),
+            Ok(Instruction::Add(Register::X, -5))
+        );
+        assert_eq!(
+            Instruction::parse("addy 3"),
+            Ok(Instruction::Add(Register(24), 3))
+        );
+        assert_eq!(Instruction::parse("jmp -2"), Ok(Instruction::Jump(-2)));
+        assert_eq!(
+            Instruction::parse("jnz a 4"),
+            Ok(Instruction::JumpNotZero(Register(0), 4))
+        );
+        assert_eq!(
+            Instruction::parse("mul x 2"),
+            Err(ParseError::Instruction("mul x 2".to_string()))
+        );
+        assert_eq!(
+            Instruction::parse("addX 2"),
+            Err(ParseError::Register("X".to_string()))
+        );
+    }
+
+    #[test]
+    fn can_observe_cycles() {
+        struct Trace(Vec<(usize, i32)>);
+
+        impl Observer for Trace {
+            fn observe(&mut self, cycle: usize, cpu: &Cpu) {
+                self.0.push((cycle, cpu.get(Register::X)));
+            }
+        }
+
+        let program = vec![
+            Instruction::Noop,
+            Instruction::Add(Register::X, 3),
+            Instruction::Add(Register::X, -5),
+        ];
+        let mut trace = Trace(Vec::new());
+        let mut cpu = Cpu::new();
+        cpu.run(&program, &mut [&mut trace]);
+
+        assert_eq!(trace.0, vec![(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]);
+        assert_eq!(cpu.get(Register::X), -1);
+    }
+
+    #[test]
+    fn can_jump() {
+        let program = vec![
+            Instruction::Add(Register(0), 3),
+            Instruction::Add(Register(0), -1),
+            Instruction::Add(Register::X, 10),
+            Instruction::JumpNotZero(Register(0), -2),
+        ];
+        let mut cpu = Cpu::new();
+        assert_eq!(cpu.get(Register(0)), 0);
+        assert_eq!(cpu.get(Register::X), 1);
+        cpu.run(&program, &mut []);
+
+        assert_eq!(cpu.get(Register(0)), 0);
+        assert_eq!(cpu.get(Register::X), 31);
+        assert_eq!(cpu.get_cycle(), 17);
+    }
+}
@@ -1,50 +1,168 @@
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
 
-pub enum Instruction {
-    AddX(i32),
-    Noop,
-}
+use super::{
+    cpu::{Cpu, Observer, Register},
+    ocr,
+};
 
 pub struct Crt {
-    screen: [[char; 40]; 6],
-    sprite: i32,
-    pixel: (usize, usize),
+    width: usize,
+    height: usize,
+    screen: Vec<bool>,
+    pixel: usize,
+}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Crt {
     pub fn new() -> Self {
+        Self::with_size(40, 6)
+    }
+
+    pub fn with_size(width: usize, height: usize) -> Self {
+        assert!(width > 0 && height > 0);
         Crt {
-            screen: [['.'; 40]; 6],
-            sprite: 1,
-            pixel: (0, 0),
+            width,
+            height,
+            screen: vec![false; width * height],
+            pixel: 0,
         }
     }
 
-    pub fn tick(&mut self) {
-        if self.pixel.1 as i32 >= self.sprite - 1 && self.pixel.1 as i32 <= self.sprite + 1 {
-            self.screen[self.pixel.0][self.pixel.1] = '#';
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    /// Row-major, `true` for lit pixels.
+    pub fn get_pixels(&self) -> &[bool] {
+        &self.screen
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> bool {
+        assert!(x < self.width && y < self.height);
+        self.screen[y * self.width + x]
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.pixel == self.screen.len()
+    }
+
+    /// Draws the next pixel, returns false without drawing anything once the screen is full.
+    pub fn draw(&mut self, sprite: i32) -> bool {
+        if self.is_full() {
+            return false;
         }
 
-        self.pixel.1 += 1;
-        if self.pixel.1 % 40 == 0 {
-            self.pixel = (self.pixel.0 + 1, 0);
+        let column = (self.pixel % self.width) as i32;
+        if column >= sprite - 1 && column <= sprite + 1 {
+            self.screen[self.pixel] = true;
         }
+
+        self.pixel += 1;
+        true
     }
 
-    pub fn add_x(&mut self, dx: i32) {
-        self.sprite += dx
+    pub fn read(&self) -> Option<String> {
+        ocr::read(&self.to_string())
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[bool]> {
+        self.screen.chunks(self.width)
+    }
+
+    /// Plain PBM, lit pixels are black.
+    pub fn write_pbm(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "P1\n{} {}", self.width, self.height)?;
+        for row in self.rows() {
+            let line: Vec<&str> = row.iter().map(|&lit| if lit { "1" } else { "0" }).collect();
+            writeln!(out, "{}", line.join(" "))?;
+        }
+
+        Ok(())
+    }
+
+    /// Plain PGM, lit pixels are black like in the PBM.
+    pub fn write_pgm(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "P2\n{} {}\n255", self.width, self.height)?;
+        for row in self.rows() {
+            let line: Vec<&str> = row
+                .iter()
+                .map(|&lit| if lit { "0" } else { "255" })
+                .collect();
+            writeln!(out, "{}", line.join(" "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Observer for Crt {
+    fn observe(&mut self, _cycle: usize, cpu: &Cpu) {
+        self.draw(cpu.get(Register::X));
     }
 }
 
 impl Display for Crt {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let out: String = self
-            .screen
-            .iter()
-            .map(|line| line.iter().collect())
+            .rows()
+            .map(|line| {
+                line.iter()
+                    .map(|&lit| if lit { '#' } else { '.' })
+                    .collect()
+            })
             .collect::<Vec<String>>()
             .join("\n");
 
         write!(f, "\n{}", out)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crt() -> Crt {
+        let mut crt = Crt::with_size(3, 2);
+        [1, 1, 1, 5, 5, 5]
+            .into_iter()
+            .for_each(|sprite| assert!(crt.draw(sprite)));
+        crt
+    }
+
+    #[test]
+    fn can_draw_custom_size() {
+        let mut crt = crt();
+        assert!(crt.is_full());
+        assert!(!crt.draw(1));
+        assert_eq!(crt.get_pixels(), &[true, true, true, false, false, false]);
+        assert!(crt.get_pixel(2, 0));
+        assert_eq!(crt.to_string(), "\n###\n...");
+    }
+
+    #[test]
+    fn can_export_images() {
+        let crt = crt();
+        let mut pbm = Vec::new();
+        crt.write_pbm(&mut pbm).unwrap();
+        assert_eq!(String::from_utf8(pbm).unwrap(), "P1\n3 2\n1 1 1\n0 0 0\n");
+
+        let mut pgm = Vec::new();
+        crt.write_pgm(&mut pgm).unwrap();
+        assert_eq!(
+            String::from_utf8(pgm).unwrap(),
+            "P2\n3 2\n255\n0 0 0\n255 255 255\n"
+        );
+    }
+}
@@ -0,0 +1,82 @@
+const GLYPH_WIDTH: usize = 4;
+const GLYPH_HEIGHT: usize = 6;
+
+const FONT: [(char, [&str; GLYPH_HEIGHT]); 18] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+    (' ', ["....", "....", "....", "....", "....", "...."]),
+];
+
+fn recognize(glyph: &[&str]) -> Option<char> {
+    FONT.iter()
+        .find(|(_, pattern)| pattern.iter().eq(glyph.iter()))
+        .map(|(letter, _)| *letter)
+}
+
+/// Reads the letters off a rendered screen of `#` and `.`, using the 4x6 font with a column of spacing.
+/// Returns `None` if any of the glyphs is not in the font.
+pub fn read(image: &str) -> Option<String> {
+    let rows: Vec<&str> = image.lines().filter(|line| !line.is_empty()).collect();
+    if rows.len() != GLYPH_HEIGHT {
+        return None;
+    }
+
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    (0..width)
+        .step_by(GLYPH_WIDTH + 1)
+        .map(|start| {
+            let glyph: Vec<&str> = rows
+                .iter()
+                .map(|row| row.get(start..start + GLYPH_WIDTH).unwrap_or(""))
+                .collect();
+            recognize(&glyph)
+        })
+        .collect::<Option<String>>()
+        .map(|text| text.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_read_letters() {
+        let image = "
+####.#..#.###..#..#.####.###..#..#.####.
+#....#.#..#..#.#..#.#....#..#.#..#....#.
+###..##...#..#.####.###..#..#.#..#...#..
+#....#.#..###..#..#.#....###..#..#..#...
+#....#.#..#.#..#..#.#....#....#..#.#....
+####.#..#.#..#.#..#.####.#.....##..####.";
+
+        assert_eq!(read(image), Some("EKRHEPUZ".to_string()));
+    }
+
+    #[test]
+    fn cannot_read_unknown_glyphs() {
+        let image = "
+#..#.
+.##..
+.##..
+#..#.
+#..#.
+#..#.";
+
+        assert_eq!(read(image), None);
+    }
+}
@@ -1,62 +1,172 @@
-mod monkey;
+pub mod monkey;
+pub mod trace;
+pub mod troop;
+pub mod worry;
 
-use self::monkey::Monkey;
-use std::{cell::RefCell, rc::Rc};
+use self::troop::{parse_monkeys, MonkeyTroop};
+use std::error::Error;
 
-fn parse_monkeys(input: &str, reduce_worry: Rc<dyn Fn(usize) -> usize>) -> Vec<RefCell<Monkey>> {
-    input
-        .split("\n\n")
-        .map(|monkey_str| RefCell::new(Monkey::parse(monkey_str, Rc::clone(&reduce_worry))))
-        .collect()
-}
+#[aoc(day11, part1)]
+fn chill_sim(input: &str) -> Result<usize, Box<dyn Error>> {
+    let mut troop = MonkeyTroop::parse(input, Box::new(|item: usize| item / 3))?;
+    troop.simulate(20)?;
 
-fn simulate(monkeys: &Vec<RefCell<Monkey>>, rounds: u16) {
-    for _ in 1..=rounds {
-        monkeys.iter().for_each(|monkey| {
-            let mut items = monkey.borrow_mut().throw_items();
-            while let Some((item, m)) = items.pop_front() {
-                monkeys.get(m).unwrap().borrow_mut().catch_item(item);
-            }
-        })
-    }
+    Ok(troop.monkey_business())
 }
 
-fn find_two_best(monkeys: &Vec<RefCell<Monkey>>) -> (usize, usize) {
-    monkeys.iter().fold((0, 0), |mut maxes, monkey| {
-        let inspected = monkey.borrow().get_inspected();
-        if inspected > maxes.0 {
-            maxes.1 = maxes.0;
-            maxes.0 = inspected;
-        } else if inspected > maxes.1 {
-            maxes.1 = inspected;
-        }
-
-        maxes
-    })
-}
+#[aoc(day11, part2)]
+fn anxious_sim(input: &str) -> Result<usize, Box<dyn Error>> {
+    let monkeys = parse_monkeys(input)?;
+    let prod: usize = monkeys.iter().map(|(m, _)| m.get_divisor()).product();
+    let mut troop = MonkeyTroop::new(monkeys, Box::new(move |item: usize| item % prod));
 
-#[aoc(day11, part1)]
-fn chill_sim(input: &str) -> usize {
-    let monkeys = parse_monkeys(input, Rc::new(|item: usize| item / 3));
-    simulate(&monkeys, 20);
-    let two_best = find_two_best(&monkeys);
+    troop.simulate(10000)?;
 
-    two_best.0 * two_best.1
+    Ok(troop.monkey_business())
 }
 
-#[aoc(day11, part2)]
-fn anxious_sim(input: &str) -> usize {
-    let monkeys = parse_monkeys(input, Rc::new(|item: usize| item));
-    let prod = monkeys.iter().fold(1, |p, m| p * m.borrow().get_divisor());
-    let reducer = Rc::new(move |item: usize| item % prod);
+#[cfg(test)]
+mod tests {
+    use super::{monkey::ParseError, troop::Overflow, *};
+
+    const EXAMPLE: &str = "Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 2
+    If false: throw to monkey 3
+
+Monkey 1:
+  Starting items: 54, 65, 75, 74
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 2
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items: 79, 60, 97
+  Operation: new = old * old
+  Test: divisible by 13
+    If true: throw to monkey 1
+    If false: throw to monkey 3
+
+Monkey 3:
+  Starting items: 74
+  Operation: new = old + 3
+  Test: divisible by 17
+    If true: throw to monkey 0
+    If false: throw to monkey 1";
+
+    #[test]
+    fn can_solve_example() {
+        assert_eq!(chill_sim(EXAMPLE).unwrap(), 10605);
+        assert_eq!(anxious_sim(EXAMPLE).unwrap(), 2713310158);
+    }
+
+    #[test]
+    fn can_print_troop() {
+        let troop = MonkeyTroop::parse(EXAMPLE, Box::new(|item: usize| item)).unwrap();
+        assert_eq!(troop.to_string(), EXAMPLE);
+    }
+
+    #[test]
+    fn can_reject_unknown_monkeys() {
+        let stray = EXAMPLE.replace("If false: throw to monkey 3", "If false: throw to monkey 7");
+        assert_eq!(
+            parse_monkeys::<usize>(&stray).err(),
+            Some(ParseError::Target {
+                monkey: 0,
+                target: 7
+            })
+        );
+
+        let swapped = EXAMPLE.replace("Monkey 1:", "Monkey 4:");
+        assert_eq!(
+            MonkeyTroop::parse(&swapped, Box::new(|item: usize| item)).err(),
+            Some(ParseError::Id {
+                expected: 1,
+                found: 4
+            })
+        );
+    }
+
+    #[test]
+    fn can_detect_overflow() {
+        let mut troop = MonkeyTroop::parse(EXAMPLE, Box::new(|item: usize| item)).unwrap();
+        assert_eq!(
+            troop.simulate(20),
+            Err(Overflow {
+                monkey: 0,
+                round: 13
+            })
+        );
+
+        // nothing is lost: the items left to inspect stay with the monkey that overflowed
+        let held: usize = (0..4).map(|monkey| troop.get_items(monkey).len()).sum();
+        assert_eq!(held, 10);
+        assert!(!troop.get_items(0).is_empty());
+    }
+
+    #[test]
+    fn can_trace_rounds() {
+        let troop = MonkeyTroop::parse(EXAMPLE, Box::new(|item: usize| item / 3)).unwrap();
+        let rounds = trace::trace(&troop, 20).unwrap();
 
-    monkeys.iter().for_each(|m| {
-        let r = Rc::clone(&reducer);
-        m.borrow_mut().set_worry_reducer(r);
-    });
+        assert_eq!(rounds[0].paths[0], vec![0, 3]);
+        assert_eq!(rounds[0].inspected, vec![2, 4, 3, 5]);
+        assert_eq!(rounds[19].inspected, vec![101, 95, 7, 105]);
+    }
+
+    #[test]
+    fn can_extrapolate_rounds() {
+        let troop = MonkeyTroop::parse(EXAMPLE, Box::new(|item: usize| item % 96577)).unwrap();
+        let extrapolated = trace::extrapolate(&troop, 10000, 100000).unwrap();
+        assert_eq!(extrapolated, vec![52166, 47830, 1938, 52013]);
+
+        let rounds = trace::trace(&troop, 500).unwrap();
+        let extrapolated = trace::extrapolate(&troop, 500, 100000).unwrap();
+        let traced: Vec<u64> = rounds[499].inspected.iter().map(|i| *i as u64).collect();
+        assert_eq!(extrapolated, traced);
+    }
 
-    simulate(&monkeys, 10000);
-    let two_best = find_two_best(&monkeys);
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn can_simulate_big_worries() {
+        use super::worry::Worry;
+        use num_bigint::BigUint;
 
-    two_best.0 * two_best.1
+        let mut big = MonkeyTroop::parse(EXAMPLE, Box::new(|item: BigUint| item)).unwrap();
+        big.simulate(10).unwrap();
+
+        let mut small = MonkeyTroop::parse(EXAMPLE, Box::new(|item: usize| item % 96577)).unwrap();
+        small.simulate(10).unwrap();
+
+        assert_eq!(big.get_inspected(), small.get_inspected());
+
+        assert!(Worry::is_multiple_of(&BigUint::default(), 0));
+        assert!(!Worry::is_multiple_of(&BigUint::from(7usize), 0));
+    }
+
+    #[test]
+    fn can_simulate_in_parallel() {
+        let results: Vec<usize> = std::thread::scope(|s| {
+            let handles: Vec<_> = (3..=6)
+                .map(|divisor| {
+                    s.spawn(move || {
+                        let mut troop = MonkeyTroop::parse(
+                            EXAMPLE,
+                            Box::new(move |item: usize| item / divisor),
+                        )
+                        .unwrap();
+                        troop.simulate(20).unwrap();
+                        troop.monkey_business()
+                    })
+                })
+                .collect();
+
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        assert_eq!(results[0], 10605);
+    }
 }
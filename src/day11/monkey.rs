@@ -1,83 +1,402 @@
-use std::{collections::LinkedList, rc::Rc};
+use std::{error::Error, fmt::Display, iter::Peekable, num::ParseIntError, str::Chars};
 
-pub struct Monkey {
-    items: LinkedList<usize>,
-    inspected: usize,
-    operation: Box<dyn Fn(usize) -> usize>,
-    test: Box<dyn Fn(usize) -> usize>,
-    reduce_worry: Rc<dyn Fn(usize) -> usize>,
-    divisor: usize,
+use super::worry::Worry;
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    Line(String),
+    Number(ParseIntError),
+    Operator(char),
+    Token(String),
+    /// A monkey's header does not match where it is in the troop.
+    Id {
+        expected: usize,
+        found: usize,
+    },
+    /// A monkey throws to one that is not in the troop.
+    Target {
+        monkey: usize,
+        target: usize,
+    },
 }
 
-impl Monkey {
-    pub fn parse(monkey_string: &str, reduce_worry: Rc<dyn Fn(usize) -> usize>) -> Self {
-        let mut iter = monkey_string.lines();
-        iter.next();
-
-        let items = iter.next().unwrap().trim()[16..]
-            .split(", ")
-            .map(|item| item.parse().unwrap())
-            .collect();
-
-        let mut operation = iter.next().unwrap().trim()[21..].split(' ');
-        let operand = String::from(operation.next().unwrap());
-        let rhs = String::from(operation.next().unwrap());
-
-        let operation = Box::new(move |old: usize| {
-            let rhs = match rhs.as_str() {
-                "old" => old,
-                num => num.parse().unwrap(),
-            };
-
-            match operand.as_str() {
-                "+" => old + rhs,
-                "*" => old * rhs,
-                _ => unreachable!(),
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Line(line) => write!(f, "malformed line: {:?}", line),
+            ParseError::Number(err) => write!(f, "invalid number: {}", err),
+            ParseError::Operator(op) => write!(f, "unsupported operator: {:?}", op),
+            ParseError::Token(token) => write!(f, "unexpected token: {:?}", token),
+            ParseError::Id { expected, found } => {
+                write!(f, "expected monkey {}, found monkey {}", expected, found)
+            }
+            ParseError::Target { monkey, target } => {
+                write!(f, "monkey {} throws to missing monkey {}", monkey, target)
             }
-        });
+        }
+    }
+}
 
-        let divisor: usize = iter.next().unwrap().trim()[19..].parse().unwrap();
-        let t = iter.next().unwrap().trim()[25..].parse().unwrap();
-        let f = iter.next().unwrap().trim()[26..].parse().unwrap();
+impl Error for ParseError {}
 
-        let test = Box::new(move |item: usize| if item % divisor == 0 { t } else { f });
+impl From<ParseIntError> for ParseError {
+    fn from(err: ParseIntError) -> Self {
+        ParseError::Number(err)
+    }
+}
 
-        Self {
-            items,
-            inspected: 0,
-            operation,
-            test,
-            reduce_worry,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Operator {
+    fn parse(c: char) -> Result<Self, ParseError> {
+        match c {
+            '+' => Ok(Operator::Add),
+            '-' => Ok(Operator::Sub),
+            '*' => Ok(Operator::Mul),
+            '/' => Ok(Operator::Div),
+            _ => Err(ParseError::Operator(c)),
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Operator::Add | Operator::Sub => 0,
+            Operator::Mul | Operator::Div => 1,
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operator::Add => write!(f, "+"),
+            Operator::Sub => write!(f, "-"),
+            Operator::Mul => write!(f, "*"),
+            Operator::Div => write!(f, "/"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Old,
+    Const(usize),
+    Op(Operator),
+    Open,
+    Close,
+}
+
+struct Tokenizer<'a>(Peekable<Chars<'a>>);
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<Token, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.0.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let token = match self.0.next()? {
+            '(' => Ok(Token::Open),
+            ')' => Ok(Token::Close),
+            c if c.is_ascii_digit() => {
+                let mut num = String::from(c);
+                while let Some(d) = self.0.next_if(|c| c.is_ascii_digit()) {
+                    num.push(d);
+                }
+                num.parse().map(Token::Const).map_err(ParseError::from)
+            }
+            c if c.is_alphabetic() => {
+                let mut word = String::from(c);
+                while let Some(l) = self.0.next_if(|c| c.is_alphanumeric()) {
+                    word.push(l);
+                }
+                match word.as_str() {
+                    "old" => Ok(Token::Old),
+                    _ => Err(ParseError::Token(word)),
+                }
+            }
+            c => Operator::parse(c).map(Token::Op),
+        };
+
+        Some(token)
+    }
+}
+
+/// The right hand side of `new = ...`, a tree over `old` and constants.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operation {
+    Old,
+    Const(usize),
+    Binary(Box<Operation>, Operator, Box<Operation>),
+}
+
+impl Operation {
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut tokens = Tokenizer(input.chars().peekable()).peekable();
+        let operation = Self::parse_expr(&mut tokens, 0)?;
+
+        match tokens.next() {
+            None => Ok(operation),
+            Some(token) => Err(ParseError::Token(format!("{:?}", token?))),
+        }
+    }
+
+    fn parse_expr(
+        tokens: &mut Peekable<Tokenizer>,
+        min_precedence: u8,
+    ) -> Result<Self, ParseError> {
+        let mut lhs = Self::parse_atom(tokens)?;
+
+        while let Some(Ok(Token::Op(op))) = tokens.peek() {
+            let op = *op;
+            if op.precedence() < min_precedence {
+                break;
+            }
+
+            tokens.next();
+            let rhs = Self::parse_expr(tokens, op.precedence() + 1)?;
+            lhs = Operation::Binary(Box::new(lhs), op, Box::new(rhs));
+        }
+
+        match tokens.peek() {
+            Some(Err(_)) => Err(tokens.next().unwrap().unwrap_err()),
+            _ => Ok(lhs),
+        }
+    }
+
+    fn parse_atom(tokens: &mut Peekable<Tokenizer>) -> Result<Self, ParseError> {
+        match tokens.next().transpose()? {
+            Some(Token::Old) => Ok(Operation::Old),
+            Some(Token::Const(num)) => Ok(Operation::Const(num)),
+            Some(Token::Open) => {
+                let inner = Self::parse_expr(tokens, 0)?;
+                match tokens.next().transpose()? {
+                    Some(Token::Close) => Ok(inner),
+                    token => Err(ParseError::Token(format!("{:?}", token))),
+                }
+            }
+            token => Err(ParseError::Token(format!("{:?}", token))),
+        }
+    }
+
+    pub fn eval<W: Worry>(&self, old: &W) -> Option<W> {
+        match self {
+            Operation::Old => Some(old.clone()),
+            Operation::Const(num) => Some(W::from_usize(*num)),
+            Operation::Binary(lhs, op, rhs) => lhs.eval(old)?.apply(*op, &rhs.eval(old)?),
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Operation::Binary(_, op, _) => op.precedence(),
+            _ => u8::MAX,
+        }
+    }
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operation::Old => write!(f, "old"),
+            Operation::Const(num) => write!(f, "{}", num),
+            Operation::Binary(lhs, op, rhs) => {
+                if lhs.precedence() < op.precedence() {
+                    write!(f, "({})", lhs)?;
+                } else {
+                    write!(f, "{}", lhs)?;
+                }
+
+                write!(f, " {} ", op)?;
+
+                if rhs.precedence() <= op.precedence() {
+                    write!(f, "({})", rhs)
+                } else {
+                    write!(f, "{}", rhs)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Test {
+    divisor: usize,
+    if_true: usize,
+    if_false: usize,
+}
+
+impl Test {
+    pub fn new(divisor: usize, if_true: usize, if_false: usize) -> Self {
+        Test {
             divisor,
+            if_true,
+            if_false,
         }
     }
 
-    pub fn throw_items(&mut self) -> LinkedList<(usize, usize)> {
-        let mut res = LinkedList::new();
-        self.inspected += self.items.len();
+    pub fn get_targets(&self) -> [usize; 2] {
+        [self.if_true, self.if_false]
+    }
 
-        while let Some(mut item) = self.items.pop_front() {
-            item = (self.operation)(item);
-            item = (self.reduce_worry)(item);
-            res.push_back((item, (self.test)(item)));
+    pub fn target<W: Worry>(&self, item: &W) -> usize {
+        if item.is_multiple_of(self.divisor) {
+            self.if_true
+        } else {
+            self.if_false
         }
+    }
+}
 
-        res
+impl Display for Test {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "  Test: divisible by {}", self.divisor)?;
+        writeln!(f, "    If true: throw to monkey {}", self.if_true)?;
+        write!(f, "    If false: throw to monkey {}", self.if_false)
     }
+}
+
+fn strip<'a>(line: Option<&'a str>, prefix: &str) -> Result<&'a str, ParseError> {
+    let line = line.unwrap_or_default();
+    line.trim()
+        .strip_prefix(prefix)
+        .ok_or_else(|| ParseError::Line(line.to_string()))
+}
+
+/// The rules a monkey plays by. The items it holds are kept by the troop.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Monkey {
+    id: usize,
+    operation: Operation,
+    test: Test,
+}
+
+impl Monkey {
+    /// Parses a monkey along with its starting items.
+    pub fn parse<W: Worry>(monkey_string: &str) -> Result<(Self, Vec<W>), ParseError> {
+        let mut iter = monkey_string.lines();
+
+        let id = strip(iter.next(), "Monkey ")?;
+        let id = id
+            .strip_suffix(':')
+            .ok_or_else(|| ParseError::Line(id.to_string()))?
+            .parse()?;
+
+        let items = strip(iter.next(), "Starting items:")?
+            .split(',')
+            .map(|item| item.trim().parse().map(W::from_usize))
+            .collect::<Result<_, _>>()?;
+
+        let operation = Operation::parse(strip(iter.next(), "Operation: new =")?)?;
 
-    pub fn catch_item(&mut self, item: usize) {
-        self.items.push_back(item);
+        let test = Test::new(
+            strip(iter.next(), "Test: divisible by ")?.parse()?,
+            strip(iter.next(), "If true: throw to monkey ")?.parse()?,
+            strip(iter.next(), "If false: throw to monkey ")?.parse()?,
+        );
+
+        Ok((
+            Self {
+                id,
+                operation,
+                test,
+            },
+            items,
+        ))
     }
 
-    pub fn get_inspected(&self) -> usize {
-        self.inspected
+    pub fn get_id(&self) -> usize {
+        self.id
     }
 
     pub fn get_divisor(&self) -> usize {
-        self.divisor
+        self.test.divisor
+    }
+
+    pub fn get_operation(&self) -> &Operation {
+        &self.operation
+    }
+
+    pub fn get_test(&self) -> &Test {
+        &self.test
     }
+}
+
+impl Display for Monkey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Monkey {}:", self.id)?;
+        writeln!(f, "  Operation: new = {}", self.operation)?;
+        write!(f, "{}", self.test)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_operations() {
+        assert_eq!(
+            Operation::parse("old * 19"),
+            Ok(Operation::Binary(
+                Box::new(Operation::Old),
+                Operator::Mul,
+                Box::new(Operation::Const(19))
+            ))
+        );
+
+        let op = Operation::parse("(old + 2) * old - 10 / (5 - 3)").unwrap();
+        assert_eq!(op.eval(&3), Some(10));
+        assert_eq!(op.eval(&1), None);
+        assert_eq!(op.to_string(), "(old + 2) * old - 10 / (5 - 3)");
 
-    pub fn set_worry_reducer(&mut self, reducer: Rc<dyn Fn(usize) -> usize>) {
-        self.reduce_worry = reducer
+        assert_eq!(
+            Operation::parse("old - (old - 1)").unwrap().to_string(),
+            "old - (old - 1)"
+        );
+        assert_eq!(
+            Operation::parse("(old - 4) - 1").unwrap().to_string(),
+            "old - 4 - 1"
+        );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn cannot_parse_invalid_operations() {
+        assert_eq!(Operation::parse("old ^ 2"), Err(ParseError::Operator('^')));
+        assert_eq!(
+            Operation::parse("new + 2"),
+            Err(ParseError::Token("new".to_string()))
+        );
+        assert!(matches!(
+            Operation::parse("(old + 2"),
+            Err(ParseError::Token(_))
+        ));
+        assert!(matches!(
+            Operation::parse("old 2"),
+            Err(ParseError::Token(_))
+        ));
+    }
+
+    #[test]
+    fn can_parse_monkey() {
+        let input = "Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 2
+    If false: throw to monkey 3";
+
+        let (monkey, items) = Monkey::parse::<usize>(input).unwrap();
+        assert_eq!(monkey.get_test(), &Test::new(23, 2, 3));
+        assert_eq!(items, vec![79, 98]);
+        assert_eq!(
+            monkey.to_string(),
+            input.replace("  Starting items: 79, 98\n", "")
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use std::{collections::HashMap, error::Error, fmt::Display};
+
+use super::{
+    troop::{MonkeyTroop, Overflow},
+    worry::Worry,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum TraceError {
+    Overflow(Overflow),
+    Aperiodic { item: usize },
+}
+
+impl Display for TraceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraceError::Overflow(overflow) => write!(f, "{}", overflow),
+            TraceError::Aperiodic { item } => write!(f, "item {} did not repeat itself", item),
+        }
+    }
+}
+
+impl Error for TraceError {}
+
+impl From<Overflow> for TraceError {
+    fn from(overflow: Overflow) -> Self {
+        TraceError::Overflow(overflow)
+    }
+}
+
+/// Every item the monkeys hold, as `(monkey, worry)`. The index of an item is its id in traces.
+pub fn items<W: Worry>(troop: &MonkeyTroop<W>) -> Vec<(usize, W)> {
+    (0..troop.get_monkeys().len())
+        .flat_map(|monkey| {
+            troop
+                .get_items(monkey)
+                .iter()
+                .map(move |item| (monkey, item.clone()))
+        })
+        .collect()
+}
+
+/// Plays a single round for a single item. Items never affect each other, so this is exact.
+/// Returns where the item ends up, its worry level and the monkeys that inspected it.
+fn play_round<W: Worry>(
+    troop: &MonkeyTroop<W>,
+    (mut monkey, mut worry): (usize, W),
+    round: usize,
+) -> Result<(usize, W, Vec<usize>), Overflow> {
+    let mut path = Vec::new();
+
+    loop {
+        path.push(monkey);
+        let (next_worry, target) = troop
+            .inspect(monkey, &worry)
+            .ok_or(Overflow { monkey, round })?;
+
+        worry = next_worry;
+        if target <= monkey {
+            return Ok((target, worry, path));
+        }
+
+        monkey = target;
+    }
+}
+
+pub struct RoundTrace {
+    /// The monkeys that inspected each item this round, in order.
+    pub paths: Vec<Vec<usize>>,
+    /// How many items each monkey has inspected in total by the end of this round.
+    pub inspected: Vec<usize>,
+}
+
+/// Records the given number of rounds item by item, starting from the items the monkeys hold.
+/// The troop itself is left untouched.
+pub fn trace<W: Worry>(troop: &MonkeyTroop<W>, rounds: usize) -> Result<Vec<RoundTrace>, Overflow> {
+    let mut items = items(troop);
+    let mut inspected = troop.get_inspected().to_vec();
+    let mut res = Vec::with_capacity(rounds);
+
+    for round in troop.get_round() + 1..=troop.get_round() + rounds {
+        let mut paths = Vec::with_capacity(items.len());
+        for item in items.iter_mut() {
+            let (monkey, worry, path) = play_round(troop, item.clone(), round)?;
+            path.iter().for_each(|m| inspected[*m] += 1);
+            *item = (monkey, worry);
+            paths.push(path);
+        }
+
+        res.push(RoundTrace {
+            paths,
+            inspected: inspected.clone(),
+        });
+    }
+
+    Ok(res)
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Cycle {
+    /// The round the repetition starts with, rounds before it are played only once.
+    pub start: usize,
+    pub period: usize,
+    /// The monkeys that inspected the item in each of the first `start + period` rounds.
+    pub paths: Vec<Vec<usize>>,
+}
+
+impl Cycle {
+    fn path(&self, round: usize) -> &[usize] {
+        if round < self.start {
+            &self.paths[round]
+        } else {
+            &self.paths[self.start + (round - self.start) % self.period]
+        }
+    }
+
+    /// How many times each monkey inspects the item over the given number of rounds.
+    pub fn inspections(&self, monkey_count: usize, rounds: u64) -> Vec<u64> {
+        let mut res = vec![0; monkey_count];
+        let mut count = |round: usize, times: u64| {
+            self.path(round).iter().for_each(|m| res[*m] += times);
+        };
+
+        let prefix = rounds.min(self.start as u64);
+        (0..prefix as usize).for_each(|round| count(round, 1));
+
+        let rest = rounds - prefix;
+        let (full, partial) = (
+            rest / self.period as u64,
+            (rest % self.period as u64) as usize,
+        );
+        (0..self.period).for_each(|offset| {
+            let times = full + if offset < partial { 1 } else { 0 };
+            count(self.start + offset, times);
+        });
+
+        res
+    }
+}
+
+/// Plays an item's rounds until it is back in a state it has already been in.
+/// Only terminates if worry levels are kept bounded, so the search gives up after `max_rounds`.
+pub fn find_cycle<W: Worry>(
+    troop: &MonkeyTroop<W>,
+    mut item: (usize, W),
+    max_rounds: usize,
+) -> Result<Option<Cycle>, Overflow> {
+    let mut seen = HashMap::new();
+    let mut paths = Vec::new();
+
+    for round in 0..=max_rounds {
+        if let Some(start) = seen.insert(item.clone(), round) {
+            return Ok(Some(Cycle {
+                start,
+                period: round - start,
+                paths,
+            }));
+        }
+
+        let (monkey, worry, path) = play_round(troop, item, troop.get_round() + round + 1)?;
+        item = (monkey, worry);
+        paths.push(path);
+    }
+
+    Ok(None)
+}
+
+/// Counts each monkey's inspections over any number of rounds from each item's cycle.
+pub fn extrapolate<W: Worry>(
+    troop: &MonkeyTroop<W>,
+    rounds: u64,
+    max_rounds: usize,
+) -> Result<Vec<u64>, TraceError> {
+    let mut res: Vec<u64> = troop.get_inspected().iter().map(|i| *i as u64).collect();
+
+    for (id, item) in items(troop).into_iter().enumerate() {
+        let cycle =
+            find_cycle(troop, item, max_rounds)?.ok_or(TraceError::Aperiodic { item: id })?;
+        cycle
+            .inspections(res.len(), rounds)
+            .into_iter()
+            .enumerate()
+            .for_each(|(m, count)| res[m] += count);
+    }
+
+    Ok(res)
+}
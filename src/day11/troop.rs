@@ -0,0 +1,176 @@
+use std::{error::Error, fmt::Display, mem};
+
+use super::{
+    monkey::{Monkey, ParseError},
+    worry::Worry,
+};
+
+/// A worry level got too large for its type (or went negative).
+#[derive(Debug, PartialEq)]
+pub struct Overflow {
+    pub monkey: usize,
+    pub round: usize,
+}
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "worry level overflowed at monkey {} in round {}",
+            self.monkey, self.round
+        )
+    }
+}
+
+impl Error for Overflow {}
+
+pub type Reducer<W> = Box<dyn Fn(W) -> W + Send + Sync>;
+
+/// Monkeys have to be listed in order and only throw to each other.
+pub fn parse_monkeys<W: Worry>(input: &str) -> Result<Vec<(Monkey, Vec<W>)>, ParseError> {
+    let monkeys: Vec<(Monkey, Vec<W>)> = input
+        .split("\n\n")
+        .map(Monkey::parse)
+        .collect::<Result<_, _>>()?;
+
+    for (expected, (monkey, _)) in monkeys.iter().enumerate() {
+        if monkey.get_id() != expected {
+            return Err(ParseError::Id {
+                expected,
+                found: monkey.get_id(),
+            });
+        }
+
+        if let Some(target) = monkey
+            .get_test()
+            .get_targets()
+            .into_iter()
+            .find(|target| *target >= monkeys.len())
+        {
+            return Err(ParseError::Target {
+                monkey: expected,
+                target,
+            });
+        }
+    }
+
+    Ok(monkeys)
+}
+
+/// All the monkeys, the items they hold and how they calm down after an inspection.
+pub struct MonkeyTroop<W: Worry = usize> {
+    monkeys: Vec<Monkey>,
+    items: Vec<Vec<W>>,
+    inspected: Vec<usize>,
+    round: usize,
+    reduce_worry: Reducer<W>,
+}
+
+impl<W: Worry> MonkeyTroop<W> {
+    pub fn new(monkeys: Vec<(Monkey, Vec<W>)>, reduce_worry: Reducer<W>) -> Self {
+        let (monkeys, items): (Vec<Monkey>, Vec<Vec<W>>) = monkeys.into_iter().unzip();
+        MonkeyTroop {
+            inspected: vec![0; monkeys.len()],
+            monkeys,
+            items,
+            round: 0,
+            reduce_worry,
+        }
+    }
+
+    pub fn parse(input: &str, reduce_worry: Reducer<W>) -> Result<Self, ParseError> {
+        Ok(Self::new(parse_monkeys(input)?, reduce_worry))
+    }
+
+    pub fn get_monkeys(&self) -> &[Monkey] {
+        &self.monkeys
+    }
+
+    pub fn get_items(&self, monkey: usize) -> &[W] {
+        &self.items[monkey]
+    }
+
+    pub fn get_inspected(&self) -> &[usize] {
+        &self.inspected
+    }
+
+    pub fn get_round(&self) -> usize {
+        self.round
+    }
+
+    /// The new worry level of an item and the monkey it gets thrown to, without touching any state.
+    pub fn inspect(&self, monkey: usize, item: &W) -> Option<(W, usize)> {
+        let monkey = &self.monkeys[monkey];
+        let item = (self.reduce_worry)(monkey.get_operation().eval(item)?);
+        let target = monkey.get_test().target(&item);
+        Some((item, target))
+    }
+
+    /// On overflow the monkey keeps the item it failed to inspect and the ones after it, ahead of
+    /// any thrown back to it, and only the items it did throw count as inspected.
+    pub fn simulate(&mut self, rounds: usize) -> Result<(), Overflow> {
+        for _ in 0..rounds {
+            self.round += 1;
+            for monkey in 0..self.monkeys.len() {
+                let mut items = mem::take(&mut self.items[monkey]);
+
+                for (thrown, item) in items.iter().enumerate() {
+                    let Some((item, target)) = self.inspect(monkey, item) else {
+                        let mut left = items.split_off(thrown);
+                        left.append(&mut self.items[monkey]);
+                        self.items[monkey] = left;
+                        return Err(Overflow {
+                            monkey,
+                            round: self.round,
+                        });
+                    };
+                    self.inspected[monkey] += 1;
+                    self.items[target].push(item);
+                }
+
+                if self.items[monkey].is_empty() {
+                    items.clear();
+                    self.items[monkey] = items;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The product of the two largest inspection counts.
+    pub fn monkey_business(&self) -> usize {
+        let (first, second) = self.inspected.iter().fold((0, 0), |mut maxes, &inspected| {
+            if inspected > maxes.0 {
+                maxes.1 = maxes.0;
+                maxes.0 = inspected;
+            } else if inspected > maxes.1 {
+                maxes.1 = inspected;
+            }
+
+            maxes
+        });
+
+        first * second
+    }
+}
+
+impl<W: Worry> Display for MonkeyTroop<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (monkey, items)) in self.monkeys.iter().zip(self.items.iter()).enumerate() {
+            if i > 0 {
+                write!(f, "\n\n")?;
+            }
+
+            // the items go right after the monkey's header line
+            let monkey = monkey.to_string();
+            let (header, rest) = monkey.split_once('\n').unwrap_or((&monkey, ""));
+            let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+            writeln!(f, "{}", header)?;
+            writeln!(f, "  Starting items: {}", items.join(", "))?;
+            write!(f, "{}", rest)?;
+        }
+
+        Ok(())
+    }
+}
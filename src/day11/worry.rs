@@ -0,0 +1,57 @@
+use std::{fmt::Display, hash::Hash};
+
+#[cfg(feature = "bigint")]
+use num_bigint::BigUint;
+
+use super::monkey::Operator;
+
+/// A worry level. Arithmetic is checked, `None` means the result can't be represented.
+pub trait Worry: Clone + Display + Eq + Hash {
+    fn from_usize(num: usize) -> Self;
+    fn apply(&self, op: Operator, rhs: &Self) -> Option<Self>;
+    fn is_multiple_of(&self, divisor: usize) -> bool;
+}
+
+impl Worry for usize {
+    fn from_usize(num: usize) -> Self {
+        num
+    }
+
+    fn apply(&self, op: Operator, rhs: &Self) -> Option<Self> {
+        match op {
+            Operator::Add => self.checked_add(*rhs),
+            Operator::Sub => self.checked_sub(*rhs),
+            Operator::Mul => self.checked_mul(*rhs),
+            Operator::Div => self.checked_div(*rhs),
+        }
+    }
+
+    fn is_multiple_of(&self, divisor: usize) -> bool {
+        usize::is_multiple_of(*self, divisor)
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl Worry for BigUint {
+    fn from_usize(num: usize) -> Self {
+        BigUint::from(num)
+    }
+
+    fn apply(&self, op: Operator, rhs: &Self) -> Option<Self> {
+        match op {
+            Operator::Add => Some(self + rhs),
+            Operator::Sub if self >= rhs => Some(self - rhs),
+            Operator::Mul => Some(self * rhs),
+            Operator::Div if *rhs != BigUint::default() => Some(self / rhs),
+            _ => None,
+        }
+    }
+
+    /// Only zero is a multiple of zero, same as for `usize`.
+    fn is_multiple_of(&self, divisor: usize) -> bool {
+        match divisor {
+            0 => *self == BigUint::default(),
+            _ => self % BigUint::from(divisor) == BigUint::default(),
+        }
+    }
+}
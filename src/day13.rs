@@ -1,52 +1,105 @@
-mod packet;
+pub mod packet;
 
-use self::packet::Packet;
+use self::packet::{Packet, ParseError};
 
 #[aoc_generator(day13, part1)]
-fn generate_pairs(input: &str) -> Vec<(Packet, Packet)> {
+fn generate_pairs(input: &str) -> Result<Vec<(Packet, Packet)>, ParseError> {
     input
         .split("\n\n")
         .map(|pair| {
-            let mut iter = pair.split("\n").map(|packet| Packet::parse(packet));
-            (iter.next().unwrap(), iter.next().unwrap())
+            let mut iter = pair.split('\n').map(Packet::parse);
+            let empty = || Packet::parse("");
+            Ok((
+                iter.next().unwrap_or_else(empty)?,
+                iter.next().unwrap_or_else(empty)?,
+            ))
         })
         .collect()
 }
 
 #[aoc(day13, part1)]
-fn right_order(pairs: &Vec<(Packet, Packet)>) -> usize {
+fn right_order(pairs: &[(Packet, Packet)]) -> usize {
     pairs
         .iter()
         .enumerate()
-        .fold(0, |sum, (i, (a, b))| sum + if a <= b { i + 1 } else { 0 })
+        .filter(|(_, (a, b))| a.cmp_signal(b).is_le())
+        .map(|(i, _)| i + 1)
+        .sum()
+}
+
+/// Where the dividers end up, counting from 1, once they are sorted in with the packets.
+pub fn divider_positions<N: Ord>(
+    mut packets: Vec<Packet<N>>,
+    mut dividers: Vec<Packet<N>>,
+) -> Vec<usize> {
+    packets.sort_unstable();
+    dividers.sort_unstable();
+
+    dividers
+        .iter()
+        .enumerate()
+        .map(|(i, divider)| packets.partition_point(|packet| packet < divider) + i + 1)
+        .collect()
 }
 
 #[aoc(day13, part2)]
-fn decoder_key(input: &str) -> usize {
-    let mut packets: Vec<Packet> = input
+fn decoder_key(input: &str) -> Result<usize, ParseError> {
+    let packets: Vec<Packet> = input
         .lines()
-        .filter(|line| line.len() > 0)
-        .map(|packet| Packet::parse(packet))
-        .collect();
+        .filter(|line| !line.is_empty())
+        .map(Packet::parse)
+        .collect::<Result<_, _>>()?;
+    let dividers = vec![Packet::parse("[[2]]")?, Packet::parse("[[6]]")?];
 
-    packets.push(Packet::parse("[[2]]"));
-    packets.push(Packet::parse("[[6]]"));
-    packets.sort();
+    Ok(divider_positions(packets, dividers).into_iter().product())
+}
 
-    let start = Packet::parse("[[2]]");
-    let end = Packet::parse("[[6]]");
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let start = packets
-        .iter()
-        .enumerate()
-        .find_map(|(i, packet)| if *packet == start { Some(i + 1) } else { None })
-        .unwrap();
+    const EXAMPLE: &str = "[1,1,3,1,1]
+[1,1,5,1,1]
 
-    let end = packets
-        .iter()
-        .enumerate()
-        .find_map(|(i, packet)| if *packet == end { Some(i + 1) } else { None })
-        .unwrap();
+[[1],[2,3,4]]
+[[1],4]
+
+[9]
+[[8,7,6]]
+
+[[4,4],4,4]
+[[4,4],4,4,4]
+
+[7,7,7,7]
+[7,7,7]
+
+[]
+[3]
+
+[[[]]]
+[[]]
+
+[1,[2,[3,[4,[5,6,7]]]],8,9]
+[1,[2,[3,[4,[5,6,0]]]],8,9]";
+
+    #[test]
+    fn can_solve_example() {
+        assert_eq!(right_order(&generate_pairs(EXAMPLE).unwrap()), 13);
+        assert_eq!(decoder_key(EXAMPLE), Ok(140));
+    }
+
+    #[test]
+    fn can_find_divider_positions() {
+        let packets: Vec<Packet> = EXAMPLE
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| Packet::parse(line).unwrap())
+            .collect();
+        let dividers = ["[[6]]", "[[2]]", "[]", "[[10]]"]
+            .into_iter()
+            .map(|line| Packet::parse(line).unwrap())
+            .collect();
 
-    start * end
+        assert_eq!(divider_positions(packets, dividers), vec![1, 11, 15, 20]);
+    }
 }
@@ -1,66 +1,275 @@
-use std::{cell::RefCell, collections::LinkedList};
+use std::{cmp::Ordering, error::Error, fmt::Display, iter::once, str::FromStr};
 
-#[derive(Debug, PartialEq, Eq, Ord)]
-pub enum Packet {
-    List(Vec<Box<Packet>>),
-    Num(u8),
+#[derive(Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    Unexpected(char),
+    End,
+    NumberTooLarge,
 }
 
-impl Packet {
-    pub fn parse(input: &str) -> Self {
-        let mut iter = input.chars();
-        let stack: RefCell<LinkedList<Vec<Box<Packet>>>> = RefCell::new(LinkedList::new());
-        let current_num = RefCell::new(String::new());
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub position: usize,
+    pub kind: ErrorKind,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            ErrorKind::Unexpected(c) => write!(f, "unexpected {:?}", c)?,
+            ErrorKind::End => write!(f, "unexpected end of packet")?,
+            ErrorKind::NumberTooLarge => write!(f, "number too large")?,
+        }
+
+        write!(f, " at position {}", self.position)
+    }
+}
 
-        let add_num = || {
-            if current_num.borrow().len() == 0 {
-                return;
+impl Error for ParseError {}
+
+/// Numbers can be any integer type, `u32` unless asked otherwise.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Packet<N = u32> {
+    List(Vec<Packet<N>>),
+    Num(N),
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, kind: ErrorKind) -> ParseError {
+        ParseError {
+            position: self.position,
+            kind,
+        }
+    }
+
+    /// Only ASCII is ever consumed, so the position is always at the start of a character.
+    fn unexpected(&self) -> ParseError {
+        match self.input[self.position..].chars().next() {
+            Some(c) => self.error(ErrorKind::Unexpected(c)),
+            None => self.error(ErrorKind::End),
+        }
+    }
+
+    fn peek(&self) -> Result<u8, ParseError> {
+        self.input
+            .as_bytes()
+            .get(self.position)
+            .copied()
+            .ok_or_else(|| self.error(ErrorKind::End))
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), ParseError> {
+        match self.peek()? {
+            c if c == expected => {
+                self.position += 1;
+                Ok(())
             }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn packet<N: FromStr>(&mut self) -> Result<Packet<N>, ParseError> {
+        match self.peek()? {
+            b'[' => self.list(),
+            b'0'..=b'9' => self.num(),
+            _ => Err(self.unexpected()),
+        }
+    }
 
-            stack
-                .borrow_mut()
-                .back_mut()
-                .unwrap()
-                .push(Box::new(Packet::Num(current_num.borrow().parse().unwrap())));
-            current_num.borrow_mut().clear();
-        };
-
-        while let Some(c) = iter.next() {
-            match c {
-                '[' => stack.borrow_mut().push_back(Vec::new()),
-                ']' => {
-                    add_num();
-                    if stack.borrow().len() == 1 {
-                        return Self::List(stack.borrow_mut().pop_back().unwrap());
-                    } else {
-                        let closed = stack.borrow_mut().pop_back().unwrap();
-                        stack
-                            .borrow_mut()
-                            .back_mut()
-                            .unwrap()
-                            .push(Box::new(Self::List(closed)));
+    fn list<N: FromStr>(&mut self) -> Result<Packet<N>, ParseError> {
+        self.expect(b'[')?;
+        let mut list = Vec::new();
+
+        if self.peek()? == b']' {
+            self.position += 1;
+            return Ok(Packet::List(list));
+        }
+
+        loop {
+            list.push(self.packet()?);
+            match self.peek()? {
+                b',' => self.position += 1,
+                b']' => {
+                    self.position += 1;
+                    return Ok(Packet::List(list));
+                }
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    fn num<N: FromStr>(&mut self) -> Result<Packet<N>, ParseError> {
+        let start = self.position;
+        while let Some(b'0'..=b'9') = self.input.as_bytes().get(self.position) {
+            self.position += 1;
+        }
+
+        self.input[start..self.position]
+            .parse()
+            .map(Packet::Num)
+            .map_err(|_| ParseError {
+                position: start,
+                kind: ErrorKind::NumberTooLarge,
+            })
+    }
+}
+
+impl<N: FromStr> Packet<N> {
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser { input, position: 0 };
+
+        let packet = parser.packet()?;
+        match parser.position == input.len() {
+            true => Ok(packet),
+            false => Err(parser.unexpected()),
+        }
+    }
+}
+
+impl<N: FromStr> FromStr for Packet<N> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl<N: Display> Display for Packet<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Packet::Num(num) => write!(f, "{}", num),
+            Packet::List(list) => {
+                write!(f, "[")?;
+                for (i, packet) in list.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
                     }
+                    write!(f, "{}", packet)?;
                 }
-                ',' => add_num(),
-                num => current_num.borrow_mut().push(num),
+                write!(f, "]")
             }
         }
+    }
+}
 
-        unreachable!()
+fn cmp_lists<'a, N: Ord + 'a>(
+    mut a: impl Iterator<Item = &'a Packet<N>>,
+    mut b: impl Iterator<Item = &'a Packet<N>>,
+    cmp: impl Fn(&Packet<N>, &Packet<N>) -> Ordering,
+) -> Ordering {
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => match cmp(x, y) {
+                Ordering::Equal => continue,
+                ordering => return ordering,
+            },
+        }
     }
 }
 
-impl PartialOrd for Packet {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+impl<N: Ord> Packet<N> {
+    /// The order from the puzzle, where a number compares like a list holding only that number.
+    /// This makes `2`, `[2]` and `[[2]]` equal, so it is not a total order on its own.
+    pub fn cmp_signal(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Num(a), Self::Num(b)) => a.cmp(b),
+            (Self::List(a), Self::List(b)) => cmp_lists(a.iter(), b.iter(), Self::cmp_signal),
+            (Self::List(a), Self::Num(_)) => cmp_lists(a.iter(), once(other), Self::cmp_signal),
+            (Self::Num(_), Self::List(b)) => cmp_lists(once(self), b.iter(), Self::cmp_signal),
+        }
+    }
+
+    /// Breaks the ties left by `cmp_signal`, numbers go before lists.
+    fn cmp_structure(&self, other: &Self) -> Ordering {
         match (self, other) {
-            (Self::Num(a), Self::Num(b)) => a.partial_cmp(b),
-            (Self::List(a), Self::List(b)) => a.partial_cmp(b),
-            (Self::List(a), Self::Num(b)) => a.partial_cmp(&vec![Box::new(Self::Num(*b))]),
-            (Self::Num(a), Self::List(b)) => vec![Box::new(Self::Num(*a))].partial_cmp(b),
+            (Self::Num(a), Self::Num(b)) => a.cmp(b),
+            (Self::List(a), Self::List(b)) => cmp_lists(a.iter(), b.iter(), Self::cmp_structure),
+            (Self::Num(_), Self::List(_)) => Ordering::Less,
+            (Self::List(_), Self::Num(_)) => Ordering::Greater,
+        }
+    }
+}
+
+/// Sorts like the puzzle does, only packets the puzzle can't tell apart are ordered by their shape.
+impl<N: Ord> Ord for Packet<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_signal(other)
+            .then_with(|| self.cmp_structure(other))
+    }
+}
+
+impl<N: Ord> PartialOrd for Packet<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "json")]
+mod json {
+    use serde_json::Value;
+    use std::{error::Error, fmt::Display};
+
+    use super::Packet;
+
+    /// A JSON value that is not made of arrays and non-negative integers that fit.
+    #[derive(Debug, PartialEq)]
+    pub struct InvalidPacket(pub Value);
+
+    impl Display for InvalidPacket {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "not a packet: {}", self.0)
+        }
+    }
+
+    impl Error for InvalidPacket {}
+
+    impl<N: Clone> From<&Packet<N>> for Value
+    where
+        Value: From<N>,
+    {
+        fn from(packet: &Packet<N>) -> Self {
+            match packet {
+                Packet::Num(num) => Value::from(num.clone()),
+                Packet::List(list) => {
+                    Value::Array(list.iter().map(<Value as From<&Packet<N>>>::from).collect())
+                }
+            }
+        }
+    }
+
+    impl<N: TryFrom<u64>> TryFrom<&Value> for Packet<N> {
+        type Error = InvalidPacket;
+
+        fn try_from(value: &Value) -> Result<Self, Self::Error> {
+            let invalid = || InvalidPacket(value.clone());
+            match value {
+                Value::Array(list) => list
+                    .iter()
+                    .map(Self::try_from)
+                    .collect::<Result<_, _>>()
+                    .map(Packet::List),
+                Value::Number(num) => num
+                    .as_u64()
+                    .and_then(|num| N::try_from(num).ok())
+                    .map(Packet::Num)
+                    .ok_or_else(invalid),
+                _ => Err(invalid()),
+            }
         }
     }
 }
 
+#[cfg(feature = "json")]
+pub use self::json::InvalidPacket;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -69,35 +278,148 @@ mod tests {
     fn can_parse_packet() {
         let packet = "[1,[25,6,[7]]]";
         let expected = Packet::List(vec![
-            Box::new(Packet::Num(1)),
-            Box::new(Packet::List(vec![
-                Box::new(Packet::Num(25)),
-                Box::new(Packet::Num(6)),
-                Box::new(Packet::List(vec![Box::new(Packet::Num(7))])),
-            ])),
+            Packet::Num(1),
+            Packet::List(vec![
+                Packet::Num(25),
+                Packet::Num(6),
+                Packet::List(vec![Packet::Num(7)]),
+            ]),
         ]);
 
-        assert_eq!(Packet::parse(packet), expected);
+        assert_eq!(Packet::parse(packet), Ok(expected));
     }
 
     #[test]
     fn can_parse_only_list_packet() {
         let packet = "[[[]]]";
-        let expected = Packet::List(vec![Box::new(Packet::List(vec![Box::new(Packet::List(
-            Vec::new(),
-        ))]))]);
+        let expected: Packet = Packet::List(vec![Packet::List(vec![Packet::List(Vec::new())])]);
+
+        assert_eq!(Packet::parse(packet), Ok(expected));
+    }
+
+    #[test]
+    fn can_parse_wide_numbers() {
+        let packet: Packet<u64> = Packet::parse("[4294967296]").unwrap();
+        assert_eq!(packet, Packet::List(vec![Packet::Num(4294967296)]));
+
+        assert_eq!(
+            Packet::<u8>::parse("[1,256]"),
+            Err(ParseError {
+                position: 3,
+                kind: ErrorKind::NumberTooLarge
+            })
+        );
+    }
+
+    #[test]
+    fn cannot_parse_malformed_packets() {
+        let error = |position, kind| Err(ParseError { position, kind });
+
+        assert_eq!(
+            Packet::<u32>::parse("[1,,2]"),
+            error(3, ErrorKind::Unexpected(','))
+        );
+        assert_eq!(Packet::<u32>::parse("[1,[2]"), error(6, ErrorKind::End));
+        assert_eq!(
+            Packet::<u32>::parse("[1 2]"),
+            error(2, ErrorKind::Unexpected(' '))
+        );
+        assert_eq!(
+            Packet::<u32>::parse("[1]]"),
+            error(3, ErrorKind::Unexpected(']'))
+        );
+        assert_eq!(Packet::<u32>::parse(""), error(0, ErrorKind::End));
+        assert_eq!(
+            Packet::<u32>::parse("[é]"),
+            error(1, ErrorKind::Unexpected('é'))
+        );
+        assert_eq!(
+            Packet::<u32>::parse("[1é"),
+            error(2, ErrorKind::Unexpected('é'))
+        );
+    }
+
+    #[test]
+    fn can_print_packet() {
+        let packet = "[1,[2,3],[],[[4]]]";
+        assert_eq!(Packet::<u32>::parse(packet).unwrap().to_string(), packet);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn can_convert_json() {
+        let value: serde_json::Value = serde_json::from_str("[1,[2,[]]]").unwrap();
+        let packet: Packet = Packet::try_from(&value).unwrap();
+        assert_eq!(packet.to_string(), "[1,[2,[]]]");
+        assert_eq!(serde_json::Value::from(&packet), value);
+
+        let value: serde_json::Value = serde_json::from_str("[1,-2]").unwrap();
+        assert!(Packet::<u32>::try_from(&value).is_err());
+    }
+
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self, bound: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % bound
+        }
+
+        fn packet(&mut self, depth: u32) -> Packet<u8> {
+            if depth == 0 || self.next(3) == 0 {
+                Packet::Num(self.next(3) as u8)
+            } else {
+                let len = self.next(4);
+                Packet::List((0..len).map(|_| self.packet(depth - 1)).collect())
+            }
+        }
+    }
+
+    #[test]
+    fn ordering_is_total() {
+        let mut rng = XorShift(0x2022_1213);
+        let packets: Vec<Packet<u8>> = (0..60).map(|_| rng.packet(4)).collect();
+
+        for a in packets.iter() {
+            assert_eq!(a.cmp(a), Ordering::Equal);
+            for b in packets.iter() {
+                assert_eq!(a.cmp(b), b.cmp(a).reverse(), "{} {}", a, b);
+                assert_eq!(a.cmp(b) == Ordering::Equal, a == b, "{} {}", a, b);
+                assert_eq!(a.cmp_signal(b), b.cmp_signal(a).reverse(), "{} {}", a, b);
+
+                for c in packets.iter() {
+                    if a <= b && b <= c {
+                        assert!(a <= c, "{} {} {}", a, b, c);
+                    }
+                    if a.cmp_signal(b).is_le() && b.cmp_signal(c).is_le() {
+                        assert!(a.cmp_signal(c).is_le(), "{} {} {}", a, b, c);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn can_break_signal_ties() {
+        let num: Packet = Packet::Num(2);
+        let list = Packet::parse("[2]").unwrap();
+        let nested = Packet::parse("[[2]]").unwrap();
 
-        assert_eq!(Packet::parse(packet), expected);
+        assert_eq!(num.cmp_signal(&nested), Ordering::Equal);
+        assert!(num < list && list < nested);
+        assert!(nested < Packet::parse("[3]").unwrap());
     }
 
     #[test]
     fn can_compare_packets() {
-        let p1 = Packet::parse("[1,1,3,1,1]");
-        let p2 = Packet::parse("[1,1,5,1,1]");
+        let p1: Packet = Packet::parse("[1,1,3,1,1]").unwrap();
+        let p2 = Packet::parse("[1,1,5,1,1]").unwrap();
         assert!(p1 < p2);
 
-        let p1 = Packet::parse("[1,[2,[3,[4,[5,6,7]]]],8,9]");
-        let p2 = Packet::parse("[1,[2,[3,[4,[5,6,0]]]],8,9]");
+        let p1: Packet = Packet::parse("[1,[2,[3,[4,[5,6,7]]]],8,9]").unwrap();
+        let p2 = Packet::parse("[1,[2,[3,[4,[5,6,0]]]],8,9]").unwrap();
         assert!(p1 > p2);
     }
 }
@@ -0,0 +1,300 @@
+use std::fmt::Display;
+
+pub type Point = (i32, i32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bottom {
+    /// Sand that falls below the lowest rock is gone for good.
+    Abyss,
+    /// An endless floor two below the lowest rock.
+    Floor,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tile {
+    Air,
+    Rock,
+    Sand,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    Spawn(Point),
+    Move(Point),
+    Settle(Point),
+    Fall,
+}
+
+pub struct SandCave {
+    tiles: Vec<Tile>,
+    left: i32,
+    width: usize,
+    height: usize,
+    bottom: Bottom,
+    sources: Vec<Point>,
+    next_source: usize,
+    falling: Option<Point>,
+}
+
+impl SandCave {
+    /// Rock paths are lists of corners connected by straight lines, like in the input.
+    pub fn new(paths: &[Vec<Point>], sources: &[Point], bottom: Bottom) -> Self {
+        assert!(!sources.is_empty());
+        assert!(sources.iter().all(|(_, y)| *y >= 0));
+
+        let corners = || paths.iter().flatten().chain(sources.iter());
+        let lowest = corners().map(|(_, y)| *y).max().unwrap();
+        let height = match bottom {
+            Bottom::Abyss => lowest + 1,
+            Bottom::Floor => lowest + 2,
+        };
+
+        // on a floor, sand piles up in a triangle below each source
+        let spread = match bottom {
+            Bottom::Abyss => 0,
+            Bottom::Floor => height,
+        };
+        let left = corners()
+            .map(|(x, _)| *x)
+            .min()
+            .unwrap()
+            .min(sources.iter().map(|(x, y)| x - (spread - y)).min().unwrap());
+        let right = corners()
+            .map(|(x, _)| *x)
+            .max()
+            .unwrap()
+            .max(sources.iter().map(|(x, y)| x + (spread - y)).max().unwrap());
+
+        let width = (right - left + 1) as usize;
+        let mut cave = SandCave {
+            tiles: vec![Tile::Air; width * height as usize],
+            left,
+            width,
+            height: height as usize,
+            bottom,
+            sources: sources.to_vec(),
+            next_source: 0,
+            falling: None,
+        };
+
+        for path in paths {
+            for line in path.windows(2) {
+                let ((x1, y1), (x2, y2)) = (line[0], line[1]);
+                for x in x1.min(x2)..=x1.max(x2) {
+                    for y in y1.min(y2)..=y1.max(y2) {
+                        cave.set((x, y), Tile::Rock);
+                    }
+                }
+            }
+
+            if let [(x, y)] = path[..] {
+                cave.set((x, y), Tile::Rock);
+            }
+        }
+
+        cave
+    }
+
+    pub fn get_bottom(&self) -> Bottom {
+        self.bottom
+    }
+
+    pub fn get_sources(&self) -> &[Point] {
+        &self.sources
+    }
+
+    fn index(&self, (x, y): Point) -> Option<usize> {
+        let col = x - self.left;
+        if col < 0 || col as usize >= self.width || y < 0 || y as usize >= self.height {
+            return None;
+        }
+
+        Some(y as usize * self.width + col as usize)
+    }
+
+    /// Anything outside the grid is air, except for the floor.
+    pub fn get(&self, point: Point) -> Tile {
+        match self.index(point) {
+            Some(i) => self.tiles[i],
+            None if self.bottom == Bottom::Floor && point.1 == self.height as i32 => Tile::Rock,
+            None => Tile::Air,
+        }
+    }
+
+    fn set(&mut self, point: Point, tile: Tile) {
+        let i = self.index(point).unwrap();
+        self.tiles[i] = tile;
+    }
+
+    pub fn count_sand(&self) -> usize {
+        self.tiles
+            .iter()
+            .filter(|tile| **tile == Tile::Sand)
+            .count()
+    }
+
+    /// Sources take turns, skipping the ones already buried in sand.
+    fn spawn(&mut self) -> Option<Point> {
+        for _ in 0..self.sources.len() {
+            let source = self.sources[self.next_source];
+            self.next_source = (self.next_source + 1) % self.sources.len();
+            if self.get(source) == Tile::Air {
+                return Some(source);
+            }
+        }
+
+        None
+    }
+
+    /// Moves the falling grain by one cell, or spawns a new one if there is none.
+    /// Returns `None` once every source is clogged.
+    pub fn step(&mut self) -> Option<Step> {
+        let Some((x, y)) = self.falling else {
+            let source = self.spawn()?;
+            self.falling = Some(source);
+            return Some(Step::Spawn(source));
+        };
+
+        for next in [(x, y + 1), (x - 1, y + 1), (x + 1, y + 1)] {
+            if self.get(next) != Tile::Air {
+                continue;
+            }
+
+            if self.index(next).is_none() {
+                self.falling = None;
+                return Some(Step::Fall);
+            }
+
+            self.falling = Some(next);
+            return Some(Step::Move(next));
+        }
+
+        self.set((x, y), Tile::Sand);
+        self.falling = None;
+        Some(Step::Settle((x, y)))
+    }
+
+    /// Drops grains one move at a time, ending when a grain falls into the abyss or the sources clog.
+    pub fn steps(&mut self) -> Steps<'_> {
+        Steps {
+            cave: self,
+            done: false,
+        }
+    }
+
+    /// Yields where each grain settles.
+    pub fn grains(&mut self) -> impl Iterator<Item = Point> + '_ {
+        self.steps().filter_map(|step| match step {
+            Step::Settle(point) => Some(point),
+            _ => None,
+        })
+    }
+}
+
+pub struct Steps<'a> {
+    cave: &'a mut SandCave,
+    done: bool,
+}
+
+impl<'a> Iterator for Steps<'a> {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let step = self.cave.step();
+        self.done = matches!(step, None | Some(Step::Fall));
+        step
+    }
+}
+
+impl Display for SandCave {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let interesting = (0..self.tiles.len())
+            .filter(|i| self.tiles[*i] != Tile::Air)
+            .map(|i| self.left + (i % self.width) as i32)
+            .chain(self.sources.iter().map(|(x, _)| *x));
+        let (left, right) =
+            interesting.fold((i32::MAX, i32::MIN), |(l, r), x| (l.min(x), r.max(x)));
+
+        let rows = match self.bottom {
+            Bottom::Abyss => self.height,
+            Bottom::Floor => self.height + 1,
+        };
+
+        for y in 0..rows as i32 {
+            if y > 0 {
+                writeln!(f)?;
+            }
+
+            for x in left..=right {
+                let c = match self.get((x, y)) {
+                    Tile::Rock => '#',
+                    Tile::Sand => 'o',
+                    Tile::Air if self.falling == Some((x, y)) => '~',
+                    Tile::Air if self.sources.contains(&(x, y)) => '+',
+                    Tile::Air => '.',
+                };
+                write!(f, "{}", c)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<Vec<Point>> {
+        vec![
+            vec![(498, 4), (498, 6), (496, 6)],
+            vec![(503, 4), (502, 4), (502, 9), (494, 9)],
+        ]
+    }
+
+    #[test]
+    fn can_fill_into_abyss() {
+        let mut cave = SandCave::new(&example(), &[(500, 0)], Bottom::Abyss);
+        assert_eq!(cave.grains().count(), 24);
+        assert_eq!(
+            cave.to_string(),
+            "......+...
+..........
+......o...
+.....ooo..
+....#ooo##
+...o#ooo#.
+..###ooo#.
+....oooo#.
+.o.ooooo#.
+#########."
+        );
+    }
+
+    #[test]
+    fn can_fill_to_floor() {
+        let mut cave = SandCave::new(&example(), &[(500, 0)], Bottom::Floor);
+        assert_eq!(cave.grains().count(), 93);
+        assert_eq!(cave.step(), None);
+    }
+
+    #[test]
+    fn can_step_grain() {
+        let mut cave = SandCave::new(&example(), &[(500, 0)], Bottom::Abyss);
+        let steps: Vec<Step> = cave.steps().take(10).collect();
+        assert_eq!(steps[0], Step::Spawn((500, 0)));
+        assert_eq!(steps[8], Step::Move((500, 8)));
+        assert_eq!(steps[9], Step::Settle((500, 8)));
+    }
+
+    #[test]
+    fn can_take_turns_between_sources() {
+        let mut cave = SandCave::new(&[vec![(0, 2), (10, 2)]], &[(2, 0), (8, 0)], Bottom::Abyss);
+        let grains: Vec<Point> = cave.grains().take(4).collect();
+        assert_eq!(grains, vec![(2, 1), (8, 1), (1, 1), (7, 1)]);
+    }
+}
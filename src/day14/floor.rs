@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+use super::cave::Point;
+
+type Interval = (i32, i32);
+
+fn merge(mut intervals: Vec<Interval>) -> Vec<Interval> {
+    intervals.sort_unstable();
+    let mut res: Vec<Interval> = Vec::with_capacity(intervals.len());
+
+    for (start, end) in intervals {
+        match res.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+            _ => res.push((start, end)),
+        }
+    }
+
+    res
+}
+
+/// Both lists have to be sorted and free of overlaps.
+fn subtract(intervals: &[Interval], holes: &[Interval]) -> Vec<Interval> {
+    let mut res = Vec::with_capacity(intervals.len());
+    let mut holes = holes.iter().peekable();
+
+    for &(mut start, end) in intervals {
+        while let Some(&&(hole_start, hole_end)) = holes.peek() {
+            if hole_end < start {
+                holes.next();
+                continue;
+            }
+
+            if hole_start > end {
+                break;
+            }
+
+            if hole_start > start {
+                res.push((start, hole_start - 1));
+            }
+
+            start = hole_end + 1;
+            if hole_end > end {
+                break;
+            }
+
+            holes.next();
+        }
+
+        if start <= end {
+            res.push((start, end));
+        }
+    }
+
+    res
+}
+
+fn rock_rows(paths: &[Vec<Point>]) -> HashMap<i32, Vec<Interval>> {
+    let mut rows: HashMap<i32, Vec<Interval>> = HashMap::new();
+
+    for path in paths {
+        for line in path.windows(2) {
+            let ((x1, y1), (x2, y2)) = (line[0], line[1]);
+            for y in y1.min(y2)..=y1.max(y2) {
+                rows.entry(y).or_default().push((x1.min(x2), x1.max(x2)));
+            }
+        }
+
+        if let [(x, y)] = path[..] {
+            rows.entry(y).or_default().push((x, x));
+        }
+    }
+
+    rows.into_iter().map(|(y, row)| (y, merge(row))).collect()
+}
+
+/// How much sand settles on the floor, from the cells reachable row by row as intervals.
+pub fn count_sand(paths: &[Vec<Point>], sources: &[Point]) -> usize {
+    let rocks = rock_rows(paths);
+    let corners = || paths.iter().flatten().chain(sources.iter());
+    let floor = corners().map(|(_, y)| *y).max().unwrap() + 2;
+    let top = sources.iter().map(|(_, y)| *y).min().unwrap();
+
+    let mut reached: Vec<Interval> = Vec::new();
+    let mut count = 0;
+
+    for y in top..floor {
+        let spread = reached
+            .iter()
+            .map(|(start, end)| (start - 1, end + 1))
+            .chain(
+                sources
+                    .iter()
+                    .filter(|(_, sy)| *sy == y)
+                    .map(|(x, _)| (*x, *x)),
+            )
+            .collect();
+
+        reached = match rocks.get(&y) {
+            Some(row) => subtract(&merge(spread), row),
+            None => merge(spread),
+        };
+
+        count += reached
+            .iter()
+            .map(|(start, end)| (end - start + 1) as usize)
+            .sum::<usize>();
+    }
+
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::cave::{Bottom, SandCave};
+    use super::*;
+
+    fn simulate(paths: &[Vec<Point>], sources: &[Point]) -> usize {
+        let mut cave = SandCave::new(paths, sources, Bottom::Floor);
+        cave.grains().count()
+    }
+
+    #[test]
+    fn can_subtract_intervals() {
+        assert_eq!(
+            subtract(&[(0, 10), (15, 20)], &[(-3, 1), (4, 4), (9, 16), (20, 30)]),
+            vec![(2, 3), (5, 8), (17, 19)]
+        );
+    }
+
+    #[test]
+    fn matches_simulation() {
+        let example = vec![
+            vec![(498, 4), (498, 6), (496, 6)],
+            vec![(503, 4), (502, 4), (502, 9), (494, 9)],
+        ];
+        assert_eq!(count_sand(&example, &[(500, 0)]), 93);
+
+        let sources = [(500, 0), (505, 2), (490, 5)];
+        assert_eq!(count_sand(&example, &sources), simulate(&example, &sources));
+    }
+
+    #[test]
+    fn matches_simulation_on_generated_caves() {
+        let mut seed: u32 = 14;
+        let mut next = |bound: i32| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            ((seed >> 16) % bound as u32) as i32
+        };
+
+        for _ in 0..20 {
+            let paths: Vec<Vec<Point>> = (0..15)
+                .map(|_| {
+                    let (x, y) = (470 + next(60), 2 + next(60));
+                    if next(2) == 0 {
+                        vec![(x, y), (x + next(8), y)]
+                    } else {
+                        vec![(x, y), (x, y + next(8))]
+                    }
+                })
+                .collect();
+
+            let sources = [(500, 0), (480 + next(40), next(3))];
+            assert_eq!(count_sand(&paths, &sources), simulate(&paths, &sources));
+        }
+    }
+
+    #[test]
+    fn can_count_deep_caves() {
+        let paths = vec![vec![(400, 5000), (600, 5000)], vec![(500, 30), (500, 40)]];
+        let count = count_sand(&paths, &[(500, 0)]);
+
+        // the full triangle minus both rocks and the row shadowed by the long one
+        assert_eq!(count, 5002 * 5002 - 201 - 11 - 199);
+    }
+}
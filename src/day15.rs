@@ -1,7 +1,11 @@
-mod sensor;
+pub mod coverage;
+pub mod sensor;
 
 use self::sensor::{Point, Sensor};
-use std::cmp::Ordering;
+use std::{cmp::Ordering, collections::HashSet};
+
+const ROW: i32 = 2000000;
+const BOUND: i32 = 4000000;
 
 #[aoc_generator(day15)]
 fn input_generator(input: &str) -> Vec<Sensor> {
@@ -19,7 +23,7 @@ fn input_generator(input: &str) -> Vec<Sensor> {
 }
 
 fn find_combined_coverage(
-    sensors: &Vec<Sensor>,
+    sensors: &[Sensor],
     row: i32,
     min: Option<i32>,
     max: Option<i32>,
@@ -28,6 +32,7 @@ fn find_combined_coverage(
     let mut ranges: Vec<(i32, i32)> = sensors
         .iter()
         .filter_map(|sensor| sensor.find_row_coverage(row, min, max, include_beacons))
+        .filter(|(start, end)| start <= end)
         .collect();
 
     ranges.sort_by(|a, b| match b.0.cmp(&a.0) {
@@ -36,7 +41,9 @@ fn find_combined_coverage(
     });
 
     let mut combined = Vec::new();
-    let mut current = ranges.pop().unwrap();
+    let Some(mut current) = ranges.pop() else {
+        return combined;
+    };
 
     while let Some(range) = ranges.pop() {
         if range.1 <= current.1 {
@@ -55,26 +62,195 @@ fn find_combined_coverage(
     combined
 }
 
-#[aoc(day15, part1)]
-fn check_row(sensors: &Vec<Sensor>) -> u32 {
-    find_combined_coverage(sensors, 2000000, None, None, false)
+/// How many positions in the row cannot hold a beacon.
+pub fn count_covered(sensors: &[Sensor], row: i32) -> u32 {
+    find_combined_coverage(sensors, row, None, None, false)
         .into_iter()
         .fold(0, |sum, (min, max)| sum + (max - min) as u32 + 1)
 }
 
+/// The diagonal lines right outside a sensor's range, as `x + y = sum` and `x - y = diff`.
+fn borders(sensor: &Sensor) -> ([i32; 2], [i32; 2]) {
+    let (x, y) = (sensor.get_position().get_x(), sensor.get_position().get_y());
+    let reach = sensor.get_beacon_distance() as i32 + 1;
+
+    (
+        [x + y - reach, x + y + reach],
+        [x - y - reach, x - y + reach],
+    )
+}
+
+/// Finds a position within `0..=bound` on both axes that no sensor covers, where sensor borders
+/// cross. Takes O(n²) time, or O(n³) when no two sensors are just out of reach of each other.
+pub fn find_gap(sensors: &[Sensor], bound: i32) -> Option<Point> {
+    let mut sums = HashSet::new();
+    let mut diffs = HashSet::new();
+
+    for (i, first) in sensors.iter().enumerate() {
+        for second in sensors[i + 1..].iter() {
+            let apart = first.get_position() - second.get_position();
+            if apart != first.get_beacon_distance() + second.get_beacon_distance() + 2 {
+                continue;
+            }
+
+            let ((first_sums, first_diffs), (second_sums, second_diffs)) =
+                (borders(first), borders(second));
+            sums.extend(first_sums.iter().filter(|sum| second_sums.contains(sum)));
+            diffs.extend(
+                first_diffs
+                    .iter()
+                    .filter(|diff| second_diffs.contains(diff)),
+            );
+        }
+    }
+
+    let uncovered = |point: &Point| {
+        (0..=bound).contains(&point.get_x())
+            && (0..=bound).contains(&point.get_y())
+            && sensors.iter().all(|sensor| !sensor.covers(point))
+    };
+
+    if let Some(gap) = candidates(&sums, &diffs, bound).find(uncovered) {
+        return Some(gap);
+    }
+
+    for sensor in sensors {
+        let (sensor_sums, sensor_diffs) = borders(sensor);
+        sums.extend(sensor_sums);
+        diffs.extend(sensor_diffs);
+    }
+
+    let gap = candidates(&sums, &diffs, bound).find(uncovered);
+    gap
+}
+
+/// Where the borders cross each other, the edges of the area, and its corners.
+fn candidates<'a>(
+    sums: &'a HashSet<i32>,
+    diffs: &'a HashSet<i32>,
+    bound: i32,
+) -> impl Iterator<Item = Point> + 'a {
+    let crossings = sums.iter().flat_map(|sum| {
+        diffs
+            .iter()
+            .filter(move |diff| (sum + *diff) % 2 == 0)
+            .map(move |diff| Point::new((sum + diff) / 2, (sum - diff) / 2))
+    });
+
+    let edges = sums
+        .iter()
+        .flat_map(move |sum| {
+            [
+                Point::new(0, *sum),
+                Point::new(bound, sum - bound),
+                Point::new(*sum, 0),
+                Point::new(sum - bound, bound),
+            ]
+        })
+        .chain(diffs.iter().flat_map(move |diff| {
+            [
+                Point::new(0, -diff),
+                Point::new(bound, bound - diff),
+                Point::new(*diff, 0),
+                Point::new(diff + bound, bound),
+            ]
+        }));
+
+    let corners = [
+        Point::new(0, 0),
+        Point::new(bound, 0),
+        Point::new(0, bound),
+        Point::new(bound, bound),
+    ];
+
+    crossings.chain(edges).chain(corners)
+}
+
+fn tuning_frequency(point: &Point) -> u64 {
+    point.get_x() as u64 * 4000000 + point.get_y() as u64
+}
+
+#[aoc(day15, part1)]
+fn check_row(sensors: &[Sensor]) -> u32 {
+    count_covered(sensors, ROW)
+}
+
 #[aoc(day15, part2)]
-fn find_beacon(sensors: &Vec<Sensor>) -> u64 {
-    for row in 0..=4000000 {
-        let coverage = find_combined_coverage(sensors, row, Some(0), Some(4000000), true);
-
-        if coverage.len() > 1 {
-            return (coverage[0].1 + 1) as u64 * 4000000 + row as u64;
-        } else if coverage[0].0 == 1 {
-            return row as u64;
-        } else if coverage[0].1 == 3999999 {
-            return 4000000 * 4000000 + row as u64;
+fn find_beacon(sensors: &[Sensor]) -> Option<u64> {
+    find_gap(sensors, BOUND).map(|point| tuning_frequency(&point))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(super) const EXAMPLE: &str = "Sensor at x=2, y=18: closest beacon is at x=-2, y=15
+Sensor at x=9, y=16: closest beacon is at x=10, y=16
+Sensor at x=13, y=2: closest beacon is at x=15, y=3
+Sensor at x=12, y=14: closest beacon is at x=10, y=16
+Sensor at x=10, y=20: closest beacon is at x=10, y=16
+Sensor at x=14, y=17: closest beacon is at x=10, y=16
+Sensor at x=8, y=7: closest beacon is at x=2, y=10
+Sensor at x=2, y=0: closest beacon is at x=2, y=10
+Sensor at x=0, y=11: closest beacon is at x=2, y=10
+Sensor at x=20, y=14: closest beacon is at x=25, y=17
+Sensor at x=17, y=20: closest beacon is at x=21, y=22
+Sensor at x=16, y=7: closest beacon is at x=15, y=3
+Sensor at x=14, y=3: closest beacon is at x=15, y=3
+Sensor at x=20, y=1: closest beacon is at x=15, y=3";
+
+    #[test]
+    fn example() {
+        let sensors = input_generator(EXAMPLE);
+        assert_eq!(count_covered(&sensors, 10), 26);
+
+        let gap = find_gap(&sensors, 20).unwrap();
+        assert_eq!(gap, Point::new(14, 11));
+        assert_eq!(tuning_frequency(&gap), 56000011);
+    }
+
+    /// Four sensors on the diagonals around a position, each reaching right up to it.
+    fn surround(gap: &Point, bound: i32) -> Vec<Sensor> {
+        let distance = bound as u32 * 2 - 1;
+
+        [(-1, -1), (1, -1), (-1, 1), (1, 1)]
+            .into_iter()
+            .map(|(dx, dy)| {
+                let position = Point::new(gap.get_x() + dx * bound, gap.get_y() + dy * bound);
+                let beacon = Point::new(position.get_x() - dx * distance as i32, position.get_y());
+                Sensor::new(position, beacon, distance)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn can_find_gap_in_large_area() {
+        let bound = 100_000_000;
+        for gap in [
+            Point::new(12_345_678, 87_654_321),
+            Point::new(0, 42),
+            Point::new(bound, bound),
+        ] {
+            let sensors = surround(&gap, bound);
+            assert_eq!(find_gap(&sensors, bound), Some(gap));
         }
     }
 
-    unreachable!()
+    #[test]
+    fn can_find_gap_without_paired_sensors() {
+        let sensor = |x, y, beacon_x, beacon_y| {
+            let (position, beacon) = (Point::new(x, y), Point::new(beacon_x, beacon_y));
+            let distance = &position - &beacon;
+            Sensor::new(position, beacon, distance)
+        };
+
+        // only the corner at the origin is out of reach, and no two ranges are one apart
+        let sensors = [
+            sensor(10, 10, 10, 29),
+            sensor(20, 20, 20, 19),
+            sensor(20, 0, 20, 1),
+            sensor(0, 20, 0, 19),
+        ];
+        assert_eq!(find_gap(&sensors, 20), Some(Point::new(0, 0)));
+    }
 }
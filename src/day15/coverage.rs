@@ -0,0 +1,300 @@
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
+
+use super::{
+    find_combined_coverage,
+    sensor::{Point, Sensor},
+};
+
+/// A rectangle of positions, with all four edges included.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl Region {
+    pub fn new(left: i32, top: i32, right: i32, bottom: i32) -> Self {
+        assert!(left <= right && top <= bottom);
+        Region {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    /// The search area of the puzzle, `0..=bound` on both axes.
+    pub fn square(bound: i32) -> Self {
+        Self::new(0, 0, bound, bound)
+    }
+
+    /// The smallest region holding every sensor's range.
+    pub fn around(sensors: &[Sensor]) -> Self {
+        let extent = |sensor: &Sensor| {
+            let (position, distance) = (sensor.get_position(), sensor.get_beacon_distance() as i32);
+            (
+                position.get_x() - distance,
+                position.get_y() - distance,
+                position.get_x() + distance,
+                position.get_y() + distance,
+            )
+        };
+
+        let (left, top, right, bottom) = sensors.iter().map(extent).fold(
+            (i32::MAX, i32::MAX, i32::MIN, i32::MIN),
+            |(l, t, r, b), (left, top, right, bottom)| {
+                (l.min(left), t.min(top), r.max(right), b.max(bottom))
+            },
+        );
+
+        Self::new(left, top, right, bottom)
+    }
+
+    pub fn get_width(&self) -> u64 {
+        (self.right - self.left) as u64 + 1
+    }
+
+    pub fn get_height(&self) -> u64 {
+        (self.bottom - self.top) as u64 + 1
+    }
+
+    pub fn get_area(&self) -> u64 {
+        self.get_width() * self.get_height()
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        (self.left..=self.right).contains(&point.get_x())
+            && (self.top..=self.bottom).contains(&point.get_y())
+    }
+}
+
+fn row_coverage(sensors: &[Sensor], region: &Region, row: i32) -> Vec<(i32, i32)> {
+    find_combined_coverage(sensors, row, Some(region.left), Some(region.right), true)
+}
+
+/// How many positions in the region are within range of at least one sensor.
+pub fn covered_area(sensors: &[Sensor], region: &Region) -> u64 {
+    (region.top..=region.bottom)
+        .flat_map(|row| row_coverage(sensors, region, row))
+        .map(|(start, end)| (end - start) as u64 + 1)
+        .sum()
+}
+
+/// Every position in the region no sensor reaches, row by row.
+pub fn uncovered<'a>(
+    sensors: &'a [Sensor],
+    region: &'a Region,
+) -> impl Iterator<Item = Point> + 'a {
+    (region.top..=region.bottom).flat_map(move |row| {
+        let coverage = row_coverage(sensors, region, row);
+
+        // the gaps between the covered ranges, including the ones at either edge
+        let starts = coverage.iter().map(|(_, end)| end + 1);
+        let ends = coverage.iter().map(|(start, _)| start - 1);
+        let gaps: Vec<(i32, i32)> = [region.left]
+            .into_iter()
+            .chain(starts)
+            .zip(ends.chain([region.right]))
+            .filter(|(start, end)| start <= end)
+            .collect();
+
+        gaps.into_iter()
+            .flat_map(move |(start, end)| (start..=end).map(move |x| Point::new(x, row)))
+    })
+}
+
+/// A scaled down picture of a region, where every pixel stands for a square block of positions.
+pub struct CoverageMap {
+    region: Region,
+    scale: u32,
+    width: usize,
+    height: usize,
+    /// How many positions of each block are covered.
+    covered: Vec<u64>,
+    sensors: Vec<Point>,
+    beacons: Vec<Point>,
+}
+
+impl CoverageMap {
+    pub fn new(sensors: &[Sensor], region: Region, scale: u32) -> Self {
+        assert!(scale > 0);
+
+        let blocks = |length: u64| length.div_ceil(scale as u64) as usize;
+        let (width, height) = (blocks(region.get_width()), blocks(region.get_height()));
+        let mut covered = vec![0; width * height];
+
+        for row in region.top..=region.bottom {
+            let line = (row - region.top) as usize / scale as usize * width;
+            for (start, end) in row_coverage(sensors, &region, row) {
+                let (start, end) = ((start - region.left) as u64, (end - region.left) as u64);
+                let (first, last) = (start / scale as u64, end / scale as u64);
+
+                for block in first..=last {
+                    let block_start = (block * scale as u64).max(start);
+                    let block_end = ((block + 1) * scale as u64 - 1).min(end);
+                    covered[line + block as usize] += block_end - block_start + 1;
+                }
+            }
+        }
+
+        CoverageMap {
+            sensors: sensors.iter().map(|s| s.get_position().clone()).collect(),
+            beacons: sensors
+                .iter()
+                .map(|s| s.get_closest_beacon().clone())
+                .collect(),
+            region,
+            scale,
+            width,
+            height,
+            covered,
+        }
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    /// The number of positions of the region that fall into a block, smaller along the edges.
+    fn block_area(&self, x: usize, y: usize) -> u64 {
+        let scale = self.scale as u64;
+        let width = scale.min(self.region.get_width() - x as u64 * scale);
+        let height = scale.min(self.region.get_height() - y as u64 * scale);
+        width * height
+    }
+
+    /// The share of positions in a block that some sensor reaches, from 0 to 1.
+    pub fn get_coverage(&self, x: usize, y: usize) -> f64 {
+        self.covered[y * self.width + x] as f64 / self.block_area(x, y) as f64
+    }
+
+    fn block(&self, point: &Point) -> Option<(usize, usize)> {
+        if !self.region.contains(point) {
+            return None;
+        }
+
+        let x = (point.get_x() - self.region.left) as usize / self.scale as usize;
+        let y = (point.get_y() - self.region.top) as usize / self.scale as usize;
+        Some((x, y))
+    }
+
+    fn marks(&self, points: &[Point]) -> Vec<bool> {
+        let mut res = vec![false; self.width * self.height];
+        for (x, y) in points.iter().filter_map(|point| self.block(point)) {
+            res[y * self.width + x] = true;
+        }
+
+        res
+    }
+
+    /// Covered blocks are dark, uncovered ones white, with sensors and beacons in black.
+    pub fn write_pgm(&self, out: &mut impl Write) -> io::Result<()> {
+        let sensors = self.marks(&self.sensors);
+        let beacons = self.marks(&self.beacons);
+
+        writeln!(out, "P2\n{} {}\n255", self.width, self.height)?;
+        for y in 0..self.height {
+            let line: Vec<String> = (0..self.width)
+                .map(|x| {
+                    let i = y * self.width + x;
+                    if sensors[i] || beacons[i] {
+                        0
+                    } else {
+                        255 - (191.0 * self.get_coverage(x, y)).round() as u8
+                    }
+                })
+                .map(|gray| gray.to_string())
+                .collect();
+            writeln!(out, "{}", line.join(" "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `S` and `B` mark blocks with a sensor or a beacon. Otherwise blocks are `#` when fully
+/// covered, `.` when not covered at all and `?` when they hide some gaps.
+impl Display for CoverageMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sensors = self.marks(&self.sensors);
+        let beacons = self.marks(&self.beacons);
+
+        for y in 0..self.height {
+            if y > 0 {
+                writeln!(f)?;
+            }
+
+            for x in 0..self.width {
+                let i = y * self.width + x;
+                let c = if sensors[i] {
+                    'S'
+                } else if beacons[i] {
+                    'B'
+                } else if self.covered[i] == self.block_area(x, y) {
+                    '#'
+                } else if self.covered[i] == 0 {
+                    '.'
+                } else {
+                    '?'
+                };
+                write!(f, "{}", c)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{input_generator, tests::EXAMPLE};
+    use super::*;
+
+    #[test]
+    fn can_measure_coverage() {
+        let sensors = input_generator(EXAMPLE);
+        let region = Region::square(20);
+
+        assert_eq!(covered_area(&sensors, &region), 21 * 21 - 1);
+        assert_eq!(
+            uncovered(&sensors, &region).collect::<Vec<_>>(),
+            vec![Point::new(14, 11)]
+        );
+
+        let around = Region::around(&sensors);
+        assert_eq!(around, Region::new(-8, -10, 28, 26));
+        assert_eq!(
+            covered_area(&sensors, &around) + uncovered(&sensors, &around).count() as u64,
+            around.get_area()
+        );
+    }
+
+    #[test]
+    fn can_render_coverage() {
+        let sensors = input_generator(EXAMPLE);
+        let map = CoverageMap::new(&sensors, Region::square(20), 3);
+        assert_eq!(
+            map.to_string(),
+            "S###S#S
+####SB#
+##S##S#
+S###?##
+####S#S
+###SS##
+S##S#S#"
+        );
+
+        let mut pgm = Vec::new();
+        map.write_pgm(&mut pgm).unwrap();
+        assert!(pgm.starts_with(b"P2\n7 7\n255\n"));
+    }
+}
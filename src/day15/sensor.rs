@@ -7,6 +7,18 @@ pub struct Point {
 }
 
 impl Point {
+    pub fn new(x: i32, y: i32) -> Self {
+        Point { x, y }
+    }
+
+    pub fn get_x(&self) -> i32 {
+        self.x
+    }
+
+    pub fn get_y(&self) -> i32 {
+        self.y
+    }
+
     pub fn parse(point: &str) -> Self {
         let mut parts = point.split(", ");
         Self {
@@ -40,6 +52,22 @@ impl Sensor {
         }
     }
 
+    pub fn get_position(&self) -> &Point {
+        &self.position
+    }
+
+    pub fn get_closest_beacon(&self) -> &Point {
+        &self.closest_beacon
+    }
+
+    pub fn get_beacon_distance(&self) -> u32 {
+        self.beacon_distance
+    }
+
+    pub fn covers(&self, point: &Point) -> bool {
+        &self.position - point <= self.beacon_distance
+    }
+
     pub fn find_row_coverage(
         &self,
         row: i32,
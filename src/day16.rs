@@ -1,4 +1,15 @@
-use std::collections::{BTreeSet, HashMap, HashSet, LinkedList};
+pub mod plan;
+pub mod tunnels;
+pub mod valves;
+
+use self::{
+    plan::{Opening, Plan},
+    tunnels::{GraphError, Tunnels},
+    valves::{subset_maxes, Valves},
+};
+use std::collections::HashMap;
+
+type Rates = HashMap<String, u16>;
 
 fn parse_graph(input: &str) -> HashMap<String, Vec<String>> {
     input
@@ -11,10 +22,7 @@ fn parse_graph(input: &str) -> HashMap<String, Vec<String>> {
             let name = &first[6..8];
             let start = if second.contains("valves") { 23 } else { 22 };
 
-            let valves: Vec<String> = second[start..]
-                .split(", ")
-                .map(|p| String::from(p))
-                .collect();
+            let valves: Vec<String> = second[start..].split(", ").map(String::from).collect();
 
             (name, valves)
         })
@@ -24,7 +32,7 @@ fn parse_graph(input: &str) -> HashMap<String, Vec<String>> {
         })
 }
 
-fn parse_rates(input: &str) -> HashMap<String, u16> {
+fn parse_rates(input: &str) -> Rates {
     input
         .lines()
         .map(|line| {
@@ -41,205 +49,194 @@ fn parse_rates(input: &str) -> HashMap<String, u16> {
         })
 }
 
-fn find_shortest_routes(
-    graph: HashMap<String, Vec<String>>,
-    rates: &HashMap<String, u16>,
-) -> HashMap<String, Vec<(String, u16)>> {
-    let mut cache = HashMap::new();
+/// The useful valves of a scan, with walking distances measured from the given start.
+pub fn parse_valves(input: &str, start: &str) -> Result<Valves, GraphError> {
+    let tunnels = Tunnels::new(&parse_graph(input))?;
 
-    let mut bfs = |start: &str, end: &str| -> u16 {
-        if let Some(cached) = cache.get(&(String::from(start), String::from(end))) {
-            return *cached;
-        }
+    Valves::new(&tunnels, &parse_rates(input), start)
+}
 
-        let mut queue = LinkedList::from([(start, 0)]);
-        let mut visited = HashSet::from([start]);
+#[aoc_generator(day16)]
+fn input_generator(input: &str) -> Result<Valves, GraphError> {
+    parse_valves(input, "AA")
+}
 
-        while let Some((current, steps)) = queue.pop_front() {
-            if current == end {
-                cache.insert((String::from(start), String::from(end)), steps);
-                cache.insert((String::from(end), String::from(start)), steps);
-                return steps;
-            }
+fn openings(valves: &Valves, path: &[(usize, u16)], agent: usize, delay: u16) -> Vec<Opening> {
+    path.iter()
+        .map(|(valve, minute)| Opening {
+            minute: delay + minute,
+            valve: String::from(valves.get_name(*valve)),
+            agent,
+        })
+        .collect()
+}
 
-            let unvisited: Vec<&str> = graph
-                .get(current)
-                .unwrap()
-                .iter()
-                .map(|next| next.as_str())
-                .filter(|next| !visited.contains(next))
-                .collect();
+/// Agents with their sets of valves taken so far, looking for the best way to hand out the rest.
+struct Assignment<'a> {
+    /// The minutes each agent gets, from the most down.
+    budgets: Vec<u16>,
+    /// For every agent but the last, the sets it can open by what they release, most first.
+    options: Vec<&'a [(u64, u32)]>,
+    /// The best the last agent releases with each set or any of its subsets.
+    last: &'a [u32],
+    all: u64,
+    best: (u32, Vec<u64>),
+}
 
-            unvisited.iter().for_each(|next| {
-                visited.insert(next);
-                queue.push_back((next, steps + 1));
-            })
-        }
+impl<'a> Assignment<'a> {
+    /// The most the later agents could still release, never more than an earlier equal agent.
+    fn bound(&self, agent: usize, released: u32) -> u32 {
+        let cap = |other: usize, most: u32| match self.budgets[other] == self.budgets[agent] {
+            true => most.min(released),
+            false => most,
+        };
+
+        let others: u32 = (agent + 1..self.options.len())
+            .map(|other| cap(other, self.options[other][0].1))
+            .sum();
+        others + cap(self.options.len(), self.last[self.all as usize])
+    }
 
-        unreachable!()
-    };
+    fn search(
+        &mut self,
+        agent: usize,
+        from: usize,
+        taken: u64,
+        released: u32,
+        sets: &mut Vec<u64>,
+    ) {
+        if agent == self.options.len() {
+            let released = released + self.last[(self.all ^ taken) as usize];
+            if released > self.best.0 {
+                let mut sets = sets.clone();
+                sets.push(self.all ^ taken);
+                self.best = (released, sets);
+            }
 
-    let names: Vec<&str> = rates.iter().map(|(name, _)| name.as_str()).collect();
-    let mut valve_graph = HashMap::new();
+            return;
+        }
 
-    for start in names.iter() {
-        let mut ends = Vec::new();
+        for i in from..self.options[agent].len() {
+            let (set, value) = self.options[agent][i];
+            if released + value + self.bound(agent, value) <= self.best.0 {
+                break;
+            }
 
-        for end in names.iter() {
-            if start == end {
+            if set & taken != 0 {
                 continue;
             }
 
-            let distance = bfs(start, end);
-            ends.push((String::from(*end), distance))
+            let next = agent + 1;
+            let from = match next < self.options.len() && self.budgets[next] == self.budgets[agent]
+            {
+                true => i,
+                false => 0,
+            };
+
+            sets.push(set);
+            self.search(next, from, taken | set, released + value, sets);
+            sets.pop();
         }
+    }
+}
+
+/// The set of valves each agent should open.
+fn split(valves: &Valves, time: u16, delays: &[u16]) -> Result<Vec<u64>, GraphError> {
+    // agents with more time go first, so the last one has the least
+    let mut order: Vec<usize> = (0..delays.len()).collect();
+    order.sort_by_key(|agent| delays[*agent]);
+    let budgets: Vec<u16> = order
+        .iter()
+        .map(|agent| time.saturating_sub(delays[*agent]))
+        .collect();
 
-        valve_graph.insert(String::from(*start), ends);
+    let mut tables: HashMap<u16, Vec<u32>> = HashMap::new();
+    for budget in budgets.iter() {
+        if !tables.contains_key(budget) {
+            tables.insert(*budget, valves.best_per_set(*budget)?);
+        }
     }
 
-    valve_graph.insert(
-        String::from("START"),
-        names
-            .into_iter()
-            .map(|end| (String::from(end), bfs("AA", end)))
-            .collect(),
-    );
+    let options: HashMap<u16, Vec<(u64, u32)>> = budgets[..budgets.len() - 1]
+        .iter()
+        .map(|budget| {
+            let best = &tables[budget];
+            let mut sets: Vec<(u64, u32)> = (0..best.len())
+                .filter(|set| *set == 0 || best[*set] > 0)
+                .map(|set| (set as u64, best[set]))
+                .collect();
+            sets.sort_by(|(_, a), (_, b)| b.cmp(a));
+            (*budget, sets)
+        })
+        .collect();
 
-    valve_graph
-}
+    let mut last = tables.remove(&budgets[budgets.len() - 1]).unwrap();
+    subset_maxes(&mut last);
 
-#[aoc_generator(day16)]
-fn input_generator(input: &str) -> (HashMap<String, Vec<(String, u16)>>, HashMap<String, u16>) {
-    let graph = parse_graph(input);
-    let rates = parse_rates(input);
+    let mut assignment = Assignment {
+        options: budgets[..budgets.len() - 1]
+            .iter()
+            .map(|budget| options[budget].as_slice())
+            .collect(),
+        budgets,
+        last: &last,
+        all: valves.all(),
+        best: (0, vec![0; delays.len()]),
+    };
+    assignment.search(0, 0, 0, 0, &mut Vec::new());
 
-    (find_shortest_routes(graph, &rates), rates)
+    let mut sets = vec![0; delays.len()];
+    for (agent, set) in order.into_iter().zip(assignment.best.1) {
+        sets[agent] = set;
+    }
+
+    Ok(sets)
 }
 
-fn dfs(
-    graph: &HashMap<String, Vec<(String, u16)>>,
-    rates: &HashMap<String, u16>,
-    current: &str,
-    time_left: u16,
-    mut released: u16,
-    opened: &mut BTreeSet<String>,
-    max_released: &mut u16,
-    all_maxes: &mut HashMap<String, u16>,
-) {
-    if current != "START" {
-        released += time_left * rates.get(current).unwrap();
-    }
+/// The best plan for agents that each spend their delay training before setting off.
+pub fn best_plan(valves: &Valves, time: u16, delays: &[u16]) -> Result<Plan, GraphError> {
+    assert!(!delays.is_empty());
 
-    if released > *max_released {
-        *max_released = released;
-    }
+    // a single agent just needs the best path through everything
+    let sets = match delays {
+        [_] => vec![valves.all()],
+        _ => split(valves, time, delays)?,
+    };
 
-    if opened.len() > 0 {
-        let mut iter = opened.iter();
-        let hash = String::from(iter.next().unwrap());
-        let hash = iter.fold(hash, |mut str, curr| {
-            str.push(':');
-            str.push_str(curr);
-            str
-        });
-
-        match all_maxes.get(&hash) {
-            None => {
-                all_maxes.insert(hash, released);
-            }
-            Some(max) => {
-                if released > *max {
-                    all_maxes.insert(hash, released);
-                }
-            }
-        }
+    let mut released = 0;
+    let mut schedule = Vec::new();
+    for (agent, (set, delay)) in sets.iter().zip(delays).enumerate() {
+        let (agent_released, path) = valves.best_path(time.saturating_sub(*delay), *set);
+        released += agent_released;
+        schedule.extend(openings(valves, &path, agent, *delay));
     }
 
-    if time_left == 0 || graph.len() == opened.len() {
-        return;
-    }
+    Ok(Plan::new(time, delays.to_vec(), released, schedule))
+}
 
-    let nexts: Vec<&(String, u16)> = graph
-        .get(current)
-        .unwrap()
-        .iter()
-        .filter(|(next, distance)| !opened.contains(next.as_str()) && time_left > distance + 1)
-        .collect();
+pub fn best_plan_alone(valves: &Valves, time: u16) -> Plan {
+    // a single agent never splits, which is the only part that can fail
+    best_plan(valves, time, &[0]).unwrap()
+}
 
-    nexts.into_iter().for_each(|(next, distance)| {
-        opened.insert(next.clone());
-        dfs(
-            graph,
-            rates,
-            &next,
-            time_left - distance - 1,
-            released,
-            opened,
-            max_released,
-            all_maxes,
-        );
-        opened.remove(next);
-    });
+pub fn best_plan_together(valves: &Valves, time: u16) -> Result<Plan, GraphError> {
+    best_plan(valves, time, &[0, 0])
 }
 
 #[aoc(day16, part1)]
-fn most_steam_alone(
-    (valve_graph, rates): &(HashMap<String, Vec<(String, u16)>>, HashMap<String, u16>),
-) -> u16 {
-    let mut max_released = 0;
-    let mut opened = BTreeSet::new();
-    let mut all = HashMap::new();
-    dfs(
-        valve_graph,
-        rates,
-        "START",
-        30,
-        0,
-        &mut opened,
-        &mut max_released,
-        &mut all,
-    );
-
-    max_released
+fn most_steam_alone(valves: &Valves) -> u32 {
+    best_plan_alone(valves, 30).released
 }
 
+/// Both of you spend the first four minutes teaching the elephant.
 #[aoc(day16, part2)]
-fn most_steam_together(
-    (valve_graph, rates): &(HashMap<String, Vec<(String, u16)>>, HashMap<String, u16>),
-) -> u16 {
-    let mut max_released = 0;
-    let mut opened = BTreeSet::new();
-    let mut all = HashMap::new();
-    dfs(
-        valve_graph,
-        rates,
-        "START",
-        26,
-        0,
-        &mut opened,
-        &mut max_released,
-        &mut all,
-    );
-
-    let mut all: Vec<(String, u16)> = all.into_iter().collect();
-    all.sort_by(|(_, a), (_, b)| b.cmp(a));
-
-    for (i, (hash, released)) in all.iter().enumerate() {
-        let set: HashSet<&str> = HashSet::from_iter(hash.split(':'));
-
-        for (hash_ele, released_ele) in all.iter().skip(i + 1) {
-            if hash_ele.split(':').all(|valve| !set.contains(valve)) {
-                max_released = max_released.max(released + released_ele);
-                break;
-            }
-        }
-    }
-
-    max_released
+fn most_steam_together(valves: &Valves) -> Result<u32, GraphError> {
+    Ok(best_plan(valves, 30, &[4, 4])?.released)
 }
 
 #[cfg(test)]
 mod tests {
+    use super::valves::{MAX_SET_VALVES, MAX_VALVES};
     use super::*;
 
     #[test]
@@ -286,22 +283,335 @@ Valve BB has flow rate=13; tunnels lead to valves CC, AA
 Valve CC has flow rate=2; tunnels lead to valves DD, BB
 Valve DD has flow rate=20; tunnels lead to valves CC, AA";
 
-        let map = find_shortest_routes(parse_graph(str), &parse_rates(str));
+        let valves = parse_valves(str, "AA").unwrap();
+        let names: Vec<&str> = (0..valves.len()).map(|i| valves.get_name(i)).collect();
+        assert_eq!(names, vec!["BB", "CC", "DD"]);
 
-        assert!(map.contains_key("BB"));
-        assert!(map.contains_key("CC"));
-        assert!(map.contains_key("DD"));
+        let distances: Vec<Vec<u16>> = (0..valves.len())
+            .map(|from| {
+                (0..valves.len())
+                    .map(|to| valves.get_distance(from, to))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(distances, vec![vec![0, 1, 2], vec![1, 0, 1], vec![2, 1, 0]]);
+    }
 
-        assert_eq!(map.get("BB").unwrap().len(), 2);
-        assert!(map.get("BB").unwrap().contains(&(String::from("CC"), 1)));
-        assert!(map.get("BB").unwrap().contains(&(String::from("DD"), 2)));
+    const EXAMPLE: &str = "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
+Valve BB has flow rate=13; tunnels lead to valves CC, AA
+Valve CC has flow rate=2; tunnels lead to valves DD, BB
+Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE
+Valve EE has flow rate=3; tunnels lead to valves FF, DD
+Valve FF has flow rate=0; tunnels lead to valves EE, GG
+Valve GG has flow rate=0; tunnels lead to valves FF, HH
+Valve HH has flow rate=22; tunnel leads to valve GG
+Valve II has flow rate=0; tunnels lead to valves AA, JJ
+Valve JJ has flow rate=21; tunnel leads to valve II";
 
-        assert_eq!(map.get("CC").unwrap().len(), 2);
-        assert!(map.get("CC").unwrap().contains(&(String::from("BB"), 1)));
-        assert!(map.get("CC").unwrap().contains(&(String::from("DD"), 1)));
+    #[test]
+    fn can_plan_alone() {
+        let valves = input_generator(EXAMPLE).unwrap();
+        let plan = best_plan_alone(&valves, 30);
 
-        assert_eq!(map.get("DD").unwrap().len(), 2);
-        assert!(map.get("DD").unwrap().contains(&(String::from("CC"), 1)));
-        assert!(map.get("DD").unwrap().contains(&(String::from("BB"), 2)));
+        assert_eq!(plan.released, 1651);
+        assert_eq!(
+            plan.openings
+                .iter()
+                .map(|opening| (opening.minute, opening.valve.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (2, "DD"),
+                (5, "BB"),
+                (9, "JJ"),
+                (17, "HH"),
+                (21, "EE"),
+                (24, "CC")
+            ]
+        );
+
+        let narrative = plan.narrative(&valves);
+        assert!(narrative.starts_with(
+            "== Minute 1 ==
+No valves are open.
+You move towards valve DD.
+
+== Minute 2 ==
+No valves are open.
+You open valve DD.
+
+== Minute 3 ==
+Valve DD is open, releasing 20 pressure.
+You move towards valve BB."
+        ));
+        assert!(narrative.ends_with(
+            "== Minute 30 ==
+Valves BB, CC, DD, EE, HH, and JJ are open, releasing 81 pressure."
+        ));
+    }
+
+    #[test]
+    fn can_plan_together() {
+        let valves = input_generator(EXAMPLE).unwrap();
+        let plan = best_plan_together(&valves, 26).unwrap();
+
+        assert_eq!(plan.released, 1707);
+        assert_eq!(plan.get_agents(), 2);
+
+        let released: u32 = plan
+            .openings
+            .iter()
+            .map(|opening| {
+                let valve = valves.index(&opening.valve).unwrap();
+                u32::from(valves.get_rate(valve) * (plan.time - opening.minute))
+            })
+            .sum();
+        assert_eq!(released, 1707);
+
+        let mut openings: Vec<(u16, &str)> = plan
+            .openings
+            .iter()
+            .map(|opening| (opening.minute, opening.valve.as_str()))
+            .collect();
+        openings.sort_unstable();
+        assert_eq!(
+            openings,
+            vec![
+                (2, "DD"),
+                (3, "JJ"),
+                (7, "BB"),
+                (7, "HH"),
+                (9, "CC"),
+                (11, "EE")
+            ]
+        );
+
+        let narrative = plan.narrative(&valves);
+        assert!(narrative.contains(
+            "== Minute 3 ==
+Valve DD is open, releasing 20 pressure."
+        ));
+        assert_eq!(narrative.matches("The elephant opens valve").count(), 3);
+    }
+
+    #[test]
+    fn can_propagate_subset_maxes() {
+        let mut best = vec![0, 5, 3, 0, 1, 0, 0, 2];
+        subset_maxes(&mut best);
+        assert_eq!(best, vec![0, 5, 3, 5, 1, 5, 3, 5]);
+    }
+
+    #[test]
+    fn can_start_anywhere() {
+        let valves = parse_valves(EXAMPLE, "JJ").unwrap();
+        let plan = best_plan(&valves, 30, &[0]).unwrap();
+
+        let best = valves.best_per_set(30).unwrap();
+        assert_eq!(plan.released, *best.iter().max().unwrap());
+        assert_eq!(plan.openings[0].valve, "JJ");
+        assert_eq!(plan.openings[0].minute, 1);
+    }
+
+    #[test]
+    fn can_plan_for_more_agents() {
+        let valves = input_generator(EXAMPLE).unwrap();
+        let (time, delays) = (26, [0, 2, 5]);
+        let plan = best_plan(&valves, time, &delays).unwrap();
+
+        // every way to hand out the valves between three agents
+        let per_agent: Vec<Vec<u32>> = delays
+            .iter()
+            .map(|delay| {
+                let mut best = valves.best_per_set(time - delay).unwrap();
+                subset_maxes(&mut best);
+                best
+            })
+            .collect();
+        let brute_force = (0..3_usize.pow(valves.len() as u32))
+            .map(|mut owners| {
+                let mut sets = [0; 3];
+                for valve in 0..valves.len() {
+                    sets[owners % 3] |= 1 << valve;
+                    owners /= 3;
+                }
+
+                (0..3)
+                    .map(|agent| per_agent[agent][sets[agent]])
+                    .sum::<u32>()
+            })
+            .max()
+            .unwrap();
+
+        assert_eq!(plan.released, brute_force);
+        assert_eq!(plan.get_agents(), 3);
+        assert!(plan
+            .openings
+            .iter()
+            .all(|opening| opening.minute > delays[opening.agent]));
+    }
+
+    #[test]
+    fn can_train_before_setting_off() {
+        let valves = input_generator(EXAMPLE).unwrap();
+        assert_eq!(best_plan(&valves, 30, &[4, 4]).unwrap().released, 1707);
+    }
+
+    /// A scan of `count` valves joined by corridors of up to two empty rooms.
+    fn generated_scan(count: usize, max_rate: u32) -> String {
+        let mut seed: u32 = 16;
+        let mut next = |bound: u32| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) % bound
+        };
+
+        let name = |first: u8, i: usize| {
+            format!(
+                "{}{}",
+                (first + i as u8 / 26) as char,
+                (b'A' + i as u8 % 26) as char
+            )
+        };
+        let mut rooms: Vec<(String, u32, Vec<String>)> = vec![(String::from("AA"), 0, Vec::new())];
+        let mut corridors = 0;
+
+        let dig =
+            |rooms: &mut Vec<(String, u32, Vec<String>)>, parent: usize, room: String, rate| {
+                rooms[parent].2.push(room.clone());
+                rooms.push((room, rate, vec![rooms[parent].0.clone()]));
+                rooms.len() - 1
+            };
+
+        for i in 0..count {
+            let mut parent = next(rooms.len() as u32) as usize;
+            for _ in 0..next(3) {
+                parent = dig(&mut rooms, parent, name(b'M', corridors), 0);
+                corridors += 1;
+            }
+
+            let rate = 1 + next(max_rate);
+            dig(&mut rooms, parent, name(b'B', i), rate);
+        }
+
+        rooms
+            .iter()
+            .map(|(name, rate, tunnels)| {
+                format!(
+                    "Valve {} has flow rate={}; tunnels lead to valves {}",
+                    name,
+                    rate,
+                    tunnels.join(", ")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn can_plan_for_many_valves() {
+        let valves = input_generator(&generated_scan(20, 25)).unwrap();
+        assert_eq!(valves.len(), 20);
+
+        let alone = best_plan_alone(&valves, 30);
+        let together = best_plan_together(&valves, 26).unwrap();
+
+        let best = valves.best_per_set(30).unwrap();
+        assert_eq!(alone.released, *best.iter().max().unwrap());
+        assert!(together.released >= best_plan_alone(&valves, 26).released);
+    }
+
+    #[test]
+    fn can_release_lots_of_pressure() {
+        let valves = input_generator(&generated_scan(10, 60000)).unwrap();
+        let plan = best_plan(&valves, 100, &[0, 0]).unwrap();
+
+        let released: u32 = plan
+            .openings
+            .iter()
+            .map(|opening| {
+                let valve = valves.index(&opening.valve).unwrap();
+                u32::from(valves.get_rate(valve)) * u32::from(plan.time - opening.minute)
+            })
+            .sum();
+        assert!(released > u32::from(u16::MAX));
+        assert_eq!(plan.released, released);
+    }
+
+    #[test]
+    fn can_refuse_too_many_valves() {
+        let valves = input_generator(&generated_scan(25, 25)).unwrap();
+        assert_eq!(
+            best_plan(&valves, 26, &[0, 0]).err(),
+            Some(GraphError::TooManyValves {
+                count: 25,
+                limit: MAX_SET_VALVES
+            })
+        );
+
+        assert_eq!(
+            input_generator(&generated_scan(65, 25)).err(),
+            Some(GraphError::TooManyValves {
+                count: 65,
+                limit: MAX_VALVES
+            })
+        );
+    }
+
+    #[test]
+    fn can_skip_unreachable_valves() {
+        let str = "Valve AA has flow rate=0; tunnels lead to valves BB
+Valve BB has flow rate=13; tunnels lead to valves AA
+Valve CC has flow rate=2; tunnels lead to valves DD
+Valve DD has flow rate=20; tunnels lead to valves CC";
+
+        let valves = parse_valves(str, "AA").unwrap();
+        assert_eq!(valves.len(), 1);
+        assert_eq!(valves.get_name(0), "BB");
+    }
+
+    #[test]
+    fn can_report_broken_tunnels() {
+        let one_way = "Valve AA has flow rate=0; tunnels lead to valves BB, CC
+Valve BB has flow rate=13; tunnels lead to valves AA
+Valve CC has flow rate=2; tunnels lead to valves DD
+Valve DD has flow rate=0; tunnels lead to valves CC";
+
+        assert_eq!(
+            parse_valves(one_way, "AA").err(),
+            Some(GraphError::Disconnected {
+                from: String::from("CC"),
+                to: String::from("BB")
+            })
+        );
+
+        let unknown = "Valve AA has flow rate=0; tunnels lead to valves BB
+Valve BB has flow rate=13; tunnels lead to valves AA, ZZ";
+
+        assert_eq!(
+            parse_valves(unknown, "AA").err(),
+            Some(GraphError::UnknownValve(String::from("ZZ")))
+        );
+    }
+
+    #[test]
+    fn can_export_dot() {
+        let str = "Valve AA has flow rate=0; tunnels lead to valves DD, BB
+Valve BB has flow rate=13; tunnels lead to valves CC, AA
+Valve CC has flow rate=2; tunnels lead to valves DD, BB
+Valve DD has flow rate=20; tunnels lead to valves CC, AA";
+
+        assert_eq!(
+            parse_valves(str, "AA").unwrap().to_dot(),
+            "graph valves {
+    start [label=\"AA\", shape=doublecircle];
+    BB [label=\"BB\\n13\"];
+    CC [label=\"CC\\n2\"];
+    DD [label=\"DD\\n20\"];
+    start -- BB [label=1];
+    start -- CC [label=2];
+    start -- DD [label=1];
+    BB -- CC [label=1];
+    BB -- DD [label=2];
+    CC -- DD [label=1];
+}"
+        );
     }
 }
@@ -0,0 +1,126 @@
+use super::valves::Valves;
+
+/// A valve being opened during the given minute, so it releases pressure from the next one on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Opening {
+    pub minute: u16,
+    pub valve: String,
+    pub agent: usize,
+}
+
+/// When each valve gets opened and by whom, within a number of minutes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Plan {
+    pub time: u16,
+    /// The minutes each agent spends before setting off.
+    pub delays: Vec<u16>,
+    pub released: u32,
+    /// Ordered by minute.
+    pub openings: Vec<Opening>,
+}
+
+fn subject(agent: usize) -> String {
+    match agent {
+        0 => String::from("You"),
+        1 => String::from("The elephant"),
+        n => format!("Elephant {}", n),
+    }
+}
+
+fn conjugate(agent: usize, verb: &str) -> String {
+    match agent {
+        0 => String::from(verb),
+        _ => format!("{}s", verb),
+    }
+}
+
+fn list(names: &[&str]) -> String {
+    match names {
+        [] => String::new(),
+        [one] => String::from(*one),
+        [first, second] => format!("{} and {}", first, second),
+        [rest @ .., last] => format!("{}, and {}", rest.join(", "), last),
+    }
+}
+
+impl Plan {
+    pub fn new(time: u16, delays: Vec<u16>, released: u32, mut openings: Vec<Opening>) -> Self {
+        openings.sort_by_key(|opening| (opening.minute, opening.agent));
+        Plan {
+            time,
+            delays,
+            released,
+            openings,
+        }
+    }
+
+    pub fn get_agents(&self) -> usize {
+        self.delays.len()
+    }
+
+    /// What happens each minute, in the style of the puzzle.
+    pub fn narrative(&self, valves: &Valves) -> String {
+        let mut minutes = Vec::with_capacity(self.time as usize);
+
+        for minute in 1..=self.time {
+            let mut lines = vec![format!("== Minute {} ==", minute)];
+
+            let mut open: Vec<&str> = self
+                .openings
+                .iter()
+                .filter(|opening| opening.minute < minute)
+                .map(|opening| opening.valve.as_str())
+                .collect();
+            open.sort_unstable();
+
+            let pressure: u32 = open
+                .iter()
+                .filter_map(|valve| valves.index(valve))
+                .map(|valve| u32::from(valves.get_rate(valve)))
+                .sum();
+            lines.push(match open.len() {
+                0 => String::from("No valves are open."),
+                1 => format!(
+                    "Valve {} is open, releasing {} pressure.",
+                    open[0], pressure
+                ),
+                _ => format!(
+                    "Valves {} are open, releasing {} pressure.",
+                    list(&open),
+                    pressure
+                ),
+            });
+
+            for agent in (0..self.get_agents()).filter(|agent| minute > self.delays[*agent]) {
+                let next = self
+                    .openings
+                    .iter()
+                    .find(|opening| opening.agent == agent && opening.minute >= minute);
+
+                if let Some(next) = next {
+                    let verb = if next.minute == minute {
+                        "open"
+                    } else {
+                        "move"
+                    };
+                    let target = if next.minute == minute {
+                        ""
+                    } else {
+                        "towards "
+                    };
+                    lines.push(format!(
+                        "{} {} {}valve {}.",
+                        subject(agent),
+                        conjugate(agent, verb),
+                        target,
+                        next.valve
+                    ));
+                }
+            }
+
+            minutes.push(lines.join("\n"));
+        }
+
+        minutes.join("\n\n")
+    }
+}
@@ -0,0 +1,89 @@
+use std::{collections::HashMap, error::Error, fmt::Display};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GraphError {
+    /// A tunnel leads to a valve the scan never describes.
+    UnknownValve(String),
+    /// Both valves are worth visiting, but there is no way from one to the other.
+    Disconnected { from: String, to: String },
+    /// More valves worth opening than a search can keep track of.
+    TooManyValves { count: usize, limit: usize },
+}
+
+impl Display for GraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphError::UnknownValve(name) => write!(f, "tunnel leads to unknown valve {}", name),
+            GraphError::Disconnected { from, to } => {
+                write!(f, "no way to walk from valve {} to valve {}", from, to)
+            }
+            GraphError::TooManyValves { count, limit } => {
+                write!(
+                    f,
+                    "{} valves worth opening, at most {} supported",
+                    count, limit
+                )
+            }
+        }
+    }
+}
+
+impl Error for GraphError {}
+
+/// Walking distances between every pair of rooms, from Floyd–Warshall over the tunnels.
+pub struct Tunnels {
+    index: HashMap<String, usize>,
+    distances: Vec<Vec<Option<u16>>>,
+}
+
+impl Tunnels {
+    pub fn new(graph: &HashMap<String, Vec<String>>) -> Result<Self, GraphError> {
+        let index: HashMap<String, usize> = graph
+            .keys()
+            .enumerate()
+            .map(|(i, name)| (name.clone(), i))
+            .collect();
+
+        let mut distances = vec![vec![None; index.len()]; index.len()];
+        for (from, tunnels) in graph {
+            let from = index[from];
+            distances[from][from] = Some(0);
+
+            for to in tunnels {
+                let to = *index
+                    .get(to)
+                    .ok_or_else(|| GraphError::UnknownValve(to.clone()))?;
+                distances[from][to] = Some(1);
+            }
+        }
+
+        for via in 0..index.len() {
+            let onwards = distances[via].clone();
+            for row in distances.iter_mut() {
+                let Some(first) = row[via] else {
+                    continue;
+                };
+
+                for (current, second) in row.iter_mut().zip(onwards.iter()) {
+                    if let Some(second) = second {
+                        let distance = first + second;
+                        if current.is_none_or(|current| distance < current) {
+                            *current = Some(distance);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(Tunnels { index, distances })
+    }
+
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.index.get(name).copied()
+    }
+
+    /// `None` if there is no way from one room to the other.
+    pub fn distance(&self, from: usize, to: usize) -> Option<u16> {
+        self.distances[from][to]
+    }
+}
@@ -0,0 +1,270 @@
+use std::cmp::Reverse;
+
+use super::{
+    tunnels::{GraphError, Tunnels},
+    Rates,
+};
+
+/// Sets of valves are bits of a `u64`.
+pub const MAX_VALVES: usize = 64;
+
+/// Tables indexed by every set of valves get too big past this.
+pub const MAX_SET_VALVES: usize = 24;
+
+/// The most released standing at a valve just opened, and the valve it was reached from.
+#[derive(Clone, Copy)]
+struct Step {
+    position: usize,
+    opened: u64,
+    released: u32,
+    from: usize,
+}
+
+impl Step {
+    fn key(&self) -> (usize, u64) {
+        (self.position, self.opened)
+    }
+}
+
+/// The valves worth opening, interned to indices so sets of them fit into a `u64`.
+pub struct Valves {
+    start: String,
+    names: Vec<String>,
+    rates: Vec<u16>,
+    /// Minutes to walk between valves. The start is the last row and never a destination.
+    distances: Vec<Vec<u16>>,
+}
+
+impl Valves {
+    /// Keeps the valves with a rate that can be reached from the start, the only ones ever opened.
+    pub fn new(tunnels: &Tunnels, rates: &Rates, start: &str) -> Result<Self, GraphError> {
+        let reach = |name: &String| {
+            let distance = tunnels.distance(tunnels.index(start)?, tunnels.index(name)?)?;
+            Some((name.clone(), distance))
+        };
+        let mut reachable: Vec<(String, u16)> = rates.keys().filter_map(reach).collect();
+        reachable.sort_unstable();
+        if reachable.len() > MAX_VALVES {
+            return Err(GraphError::TooManyValves {
+                count: reachable.len(),
+                limit: MAX_VALVES,
+            });
+        }
+
+        let (names, from_start): (Vec<String>, Vec<u16>) = reachable.into_iter().unzip();
+        let rooms: Vec<usize> = names
+            .iter()
+            .map(|name| tunnels.index(name).unwrap())
+            .collect();
+
+        let mut distances = Vec::with_capacity(names.len() + 1);
+        for (from, room) in rooms.iter().enumerate() {
+            let row = rooms
+                .iter()
+                .enumerate()
+                .map(|(to, other)| {
+                    tunnels
+                        .distance(*room, *other)
+                        .ok_or_else(|| GraphError::Disconnected {
+                            from: names[from].clone(),
+                            to: names[to].clone(),
+                        })
+                })
+                .collect::<Result<Vec<u16>, GraphError>>()?;
+            distances.push(row);
+        }
+        distances.push(from_start);
+
+        Ok(Valves {
+            start: String::from(start),
+            rates: names.iter().map(|name| rates[name]).collect(),
+            names,
+            distances,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn get_start(&self) -> &str {
+        &self.start
+    }
+
+    pub fn get_name(&self, valve: usize) -> &str {
+        &self.names[valve]
+    }
+
+    pub fn get_rate(&self, valve: usize) -> u16 {
+        self.rates[valve]
+    }
+
+    /// Minutes to walk from one valve to another.
+    pub fn get_distance(&self, from: usize, to: usize) -> u16 {
+        self.distances[from][to]
+    }
+
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|valve| valve == name)
+    }
+
+    /// The set of every valve.
+    pub fn all(&self) -> u64 {
+        u64::MAX.checked_shr(64 - self.len() as u32).unwrap_or(0)
+    }
+
+    fn start(&self) -> usize {
+        self.len()
+    }
+
+    /// The most a single agent can release opening exactly each set of valves, zero if it can't.
+    pub fn best_per_set(&self, time: u16) -> Result<Vec<u32>, GraphError> {
+        if self.len() > MAX_SET_VALVES {
+            return Err(GraphError::TooManyValves {
+                count: self.len(),
+                limit: MAX_SET_VALVES,
+            });
+        }
+
+        let mut best = vec![0; 1 << self.len()];
+        for step in self.explore(time, self.all(), false).iter().flatten() {
+            best[step.opened as usize] = best[step.opened as usize].max(step.released);
+        }
+
+        Ok(best)
+    }
+
+    /// The pressure released by the best order to open the `allowed` valves, as `(valve, minute)`.
+    pub fn best_path(&self, time: u16, allowed: u64) -> (u32, Vec<(usize, u16)>) {
+        let layers = self.explore(time, allowed, true);
+        let Some((time_left, last)) = layers
+            .iter()
+            .enumerate()
+            .flat_map(|(time_left, layer)| layer.iter().map(move |step| (time_left, step)))
+            .max_by_key(|(time_left, step)| (step.released, *time_left, Reverse(step.key())))
+        else {
+            return (0, Vec::new());
+        };
+
+        let mut time_left = time_left as u16;
+        let mut step = *last;
+        let mut path = Vec::new();
+        while step.position != self.start() {
+            path.push((step.position, time - time_left));
+
+            time_left += self.distances[step.from][step.position] + 1;
+            let layer = &layers[time_left as usize];
+            let key = (step.from, step.opened ^ 1 << step.position);
+            step = layer[layer.binary_search_by_key(&key, Step::key).unwrap()];
+        }
+        path.reverse();
+
+        (last.released, path)
+    }
+
+    /// DP over (position, minutes left, opened set); `prune` only keeps the best path intact.
+    fn explore(&self, time: u16, allowed: u64, prune: bool) -> Vec<Vec<Step>> {
+        let mut layers = vec![Vec::new(); time as usize + 1];
+        layers[time as usize].push(Step {
+            position: self.start(),
+            opened: 0,
+            released: 0,
+            from: self.start(),
+        });
+
+        let mut best = 0;
+        for time_left in (1..=time).rev() {
+            let mut layer = std::mem::take(&mut layers[time_left as usize]);
+            layer.sort_unstable_by_key(|step| (step.key(), Reverse(step.released), step.from));
+            layer.dedup_by_key(|step| step.key());
+
+            for step in layer.iter() {
+                let reachable = (0..self.len())
+                    .filter(|next| (allowed & !step.opened) & 1 << next != 0)
+                    .map(|next| (next, self.distances[step.position][next]))
+                    .filter(|(_, distance)| time_left > distance + 1)
+                    .map(|(next, distance)| (next, time_left - distance - 1));
+
+                if prune {
+                    let bound: u32 = reachable
+                        .clone()
+                        .map(|(next, left)| u32::from(left) * u32::from(self.rates[next]))
+                        .sum();
+                    if step.released + bound <= best {
+                        continue;
+                    }
+                }
+
+                for (next, left) in reachable {
+                    let released = step.released + u32::from(left) * u32::from(self.rates[next]);
+                    best = best.max(released);
+                    layers[left as usize].push(Step {
+                        position: next,
+                        opened: step.opened | 1 << next,
+                        released,
+                        from: step.position,
+                    });
+                }
+            }
+
+            layers[time_left as usize] = layer;
+        }
+
+        layers
+    }
+
+    /// The valves as a Graphviz graph, with edges labelled by walking time.
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec![String::from("graph valves {")];
+        lines.push(format!(
+            "    start [label=\"{}\", shape=doublecircle];",
+            self.start
+        ));
+
+        for (name, rate) in self.names.iter().zip(self.rates.iter()) {
+            lines.push(format!("    {} [label=\"{}\\n{}\"];", name, name, rate));
+        }
+
+        for (to, distance) in self.distances[self.start()].iter().enumerate() {
+            lines.push(format!(
+                "    start -- {} [label={}];",
+                self.names[to], distance
+            ));
+        }
+
+        for from in 0..self.len() {
+            for to in from + 1..self.len() {
+                let (there, back) = (self.distances[from][to], self.distances[to][from]);
+                let label = match there == back {
+                    true => there.to_string(),
+                    false => format!("\"{}/{}\"", there, back),
+                };
+                lines.push(format!(
+                    "    {} -- {} [label={}];",
+                    self.names[from], self.names[to], label
+                ));
+            }
+        }
+
+        lines.push(String::from("}"));
+        lines.join("\n")
+    }
+}
+
+/// Turns the best value for each exact set into the best value for each set or any of its subsets.
+pub fn subset_maxes(best: &mut [u32]) {
+    let bits = best.len().trailing_zeros();
+    for bit in 0..bits {
+        // sets with the bit are the upper half of each block, right after the same sets without
+        for block in best.chunks_mut(2 << bit) {
+            let (without, with) = block.split_at_mut(1 << bit);
+            for (set, subset) in with.iter_mut().zip(without.iter()) {
+                *set = (*set).max(*subset);
+            }
+        }
+    }
+}
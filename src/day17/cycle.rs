@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use super::tetris::{Chamber, InfiniteStorm, Rules, Wind};
+
+/// Everything that decides how the tower grows from here on.
+type State = (Vec<u64>, usize, usize);
+
+fn state(chamber: &Chamber) -> State {
+    (
+        chamber.surface(),
+        chamber.get_wind_index(),
+        chamber.get_rock_index(),
+    )
+}
+
+/// After `start` rocks, every `period` rocks add `gain` to the height.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub period: usize,
+    pub gain: usize,
+}
+
+/// Heights of a tower up to the point it starts repeating itself.
+pub struct Tower {
+    /// The height after each number of rocks, up to the end of the first full cycle.
+    heights: Vec<usize>,
+    cycle: Cycle,
+}
+
+impl Tower {
+    /// Drops rocks until the chamber is back in a state it has already been in, and then once
+    /// more around the cycle to confirm the heights repeat. Gives up after `max_rocks`.
+    pub fn new(winds: &[Wind], rules: Rules, max_rocks: usize) -> Option<Self> {
+        let mut chamber = Chamber::with_rules(InfiniteStorm::new(winds), rules);
+        let mut heights = vec![0];
+        let mut seen: HashMap<State, usize> = HashMap::new();
+
+        while heights.len() <= max_rocks {
+            chamber.drop_rock();
+            heights.push(chamber.get_height());
+
+            let rocks = heights.len() - 1;
+            let state = state(&chamber);
+            let Some(&start) = seen.get(&state) else {
+                seen.insert(state, rocks);
+                continue;
+            };
+
+            let cycle = Cycle {
+                start,
+                period: rocks - start,
+                gain: heights[rocks] - heights[start],
+            };
+
+            for _ in 0..cycle.period {
+                chamber.drop_rock();
+                heights.push(chamber.get_height());
+            }
+
+            let repeats =
+                (start..=rocks).all(|i| heights[i + cycle.period] - heights[i] == cycle.gain);
+            if repeats && self::state(&chamber) == state {
+                heights.truncate(rocks + 1);
+                return Some(Tower { heights, cycle });
+            }
+
+            seen.insert(state, rocks);
+        }
+
+        None
+    }
+
+    pub fn get_cycle(&self) -> &Cycle {
+        &self.cycle
+    }
+
+    pub fn height_after(&self, rocks: u64) -> u64 {
+        if let Some(height) = self.heights.get(rocks as usize) {
+            return *height as u64;
+        }
+
+        let Cycle {
+            start,
+            period,
+            gain,
+        } = self.cycle;
+        let cycles = (rocks - start as u64) / period as u64;
+        let offset = (rocks - start as u64) % period as u64;
+
+        self.heights[start + offset as usize] as u64 + cycles * gain as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::EXAMPLE;
+    use super::*;
+
+    #[test]
+    fn can_extrapolate_height() {
+        let winds: Vec<Wind> = EXAMPLE.chars().map(Wind::new).collect();
+        let tower = Tower::new(&winds, Rules::default(), 10000).unwrap();
+
+        assert_eq!(tower.height_after(2022), 3068);
+        assert_eq!(tower.height_after(1000000000000), 1514285714288);
+
+        let mut chamber = Chamber::new(InfiniteStorm::new(&winds));
+        for rocks in 1..=5000 {
+            chamber.drop_rock();
+            assert_eq!(tower.height_after(rocks), chamber.get_height() as u64);
+        }
+    }
+
+    #[test]
+    fn matches_direct_simulation() {
+        let winds: Vec<Wind> = EXAMPLE.chars().map(Wind::new).collect();
+        let tower = Tower::new(&winds, Rules::default(), 10000).unwrap();
+
+        let mut chamber = Chamber::new(InfiniteStorm::new(&winds));
+        (0..1000000).for_each(|_| chamber.drop_rock());
+        assert_eq!(tower.height_after(1000000), chamber.get_height() as u64);
+    }
+
+    #[test]
+    fn can_give_up() {
+        let winds: Vec<Wind> = EXAMPLE.chars().map(Wind::new).collect();
+        assert!(Tower::new(&winds, Rules::default(), 10).is_none());
+    }
+}
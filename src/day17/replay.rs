@@ -0,0 +1,176 @@
+use std::fmt::Display;
+
+use super::tetris::{Chamber, Falling, Wind};
+
+/// What happens to a falling rock, with where it is afterwards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    Spawn(Falling),
+    /// `moved` is false when a wall or another rock is in the way.
+    Push {
+        wind: Wind,
+        moved: bool,
+        rock: Falling,
+    },
+    Fall(Falling),
+    Settle(Falling),
+}
+
+impl Event {
+    pub fn get_rock(&self) -> &Falling {
+        match self {
+            Event::Spawn(rock) | Event::Fall(rock) | Event::Settle(rock) => rock,
+            Event::Push { rock, .. } => rock,
+        }
+    }
+}
+
+/// The captions of the puzzle's diagrams.
+impl Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Event::Spawn(_) => write!(f, "A new rock begins falling:"),
+            Event::Push { wind, moved, .. } => {
+                let direction = match wind {
+                    Wind::Left => "left",
+                    Wind::Right => "right",
+                };
+                match moved {
+                    true => write!(f, "Jet of gas pushes rock {}:", direction),
+                    false => write!(
+                        f,
+                        "Jet of gas pushes rock {}, but nothing happens:",
+                        direction
+                    ),
+                }
+            }
+            Event::Fall(_) => write!(f, "Rock falls 1 unit:"),
+            Event::Settle(_) => write!(f, "Rock falls 1 unit, causing it to come to rest:"),
+        }
+    }
+}
+
+/// Gets called for every event, seeing the chamber as it is right after it.
+pub trait Observer {
+    fn observe(&mut self, chamber: &Chamber, event: &Event);
+}
+
+/// Ignores everything, for dropping rocks without watching.
+impl Observer for () {
+    fn observe(&mut self, _: &Chamber, _: &Event) {}
+}
+
+/// Keeps every event in order.
+#[derive(Default)]
+pub struct EventLog {
+    pub events: Vec<Event>,
+}
+
+impl Observer for EventLog {
+    fn observe(&mut self, _: &Chamber, event: &Event) {
+        self.events.push(*event);
+    }
+}
+
+/// Draws the chamber after every event, captioned like in the puzzle.
+#[derive(Default)]
+pub struct Frames {
+    pub frames: Vec<String>,
+}
+
+impl Observer for Frames {
+    fn observe(&mut self, chamber: &Chamber, event: &Event) {
+        let falling = match event {
+            Event::Settle(_) => None,
+            event => Some(event.get_rock()),
+        };
+
+        self.frames
+            .push(format!("{}\n{}", event, chamber.render(falling)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::EXAMPLE;
+    use super::super::tetris::InfiniteStorm;
+    use super::*;
+
+    #[test]
+    fn can_draw_frames() {
+        let winds: Vec<Wind> = EXAMPLE.chars().map(Wind::new).collect();
+        let mut chamber = Chamber::new(InfiniteStorm::new(&winds));
+        let mut frames = Frames::default();
+        chamber.drop_rock_observed(&mut frames);
+
+        assert_eq!(frames.frames.len(), 9);
+        assert_eq!(
+            frames.frames[0],
+            "A new rock begins falling:
+|..@@@@.|
+|.......|
+|.......|
+|.......|
++-------+"
+        );
+        assert_eq!(
+            frames.frames[3],
+            "Jet of gas pushes rock right, but nothing happens:
+|...@@@@|
+|.......|
+|.......|
++-------+"
+        );
+        assert_eq!(
+            frames.frames[8],
+            "Rock falls 1 unit, causing it to come to rest:
+|..####.|
++-------+"
+        );
+    }
+
+    #[test]
+    fn can_replay_rocks() {
+        let winds: Vec<Wind> = EXAMPLE.chars().map(Wind::new).collect();
+        let mut chamber = Chamber::new(InfiniteStorm::new(&winds));
+        let mut log = EventLog::default();
+        (0..10).for_each(|_| chamber.drop_rock_observed(&mut log));
+
+        assert_eq!(
+            chamber.render(None),
+            "|....#..|
+|....#..|
+|....##.|
+|##..##.|
+|######.|
+|.###...|
+|..#....|
+|.####..|
+|....##.|
+|....##.|
+|....#..|
+|..#.#..|
+|..#.#..|
+|#####..|
+|..###..|
+|...#...|
+|..####.|
++-------+"
+        );
+
+        let settled = log
+            .events
+            .iter()
+            .filter(|event| matches!(event, Event::Settle(_)))
+            .count();
+        assert_eq!(settled, 10);
+        assert_eq!(
+            log.events[0],
+            Event::Spawn(Falling {
+                rock: 0,
+                x: 2,
+                y: 3
+            })
+        );
+    }
+}
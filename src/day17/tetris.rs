@@ -1,51 +1,206 @@
-use std::fmt::Display;
-
-#[derive(Debug, PartialEq, Eq, Hash)]
-pub enum Rock {
-    Horizontal,
-    Cross,
-    Corner,
-    Vertical,
-    Square,
+use std::{error::Error, fmt::Display};
+
+use super::replay::{Event, Observer};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ShapeError {
+    Empty,
+    Cell(char),
 }
 
-impl Rock {
-    fn coords(&self) -> Vec<(usize, usize)> {
+impl Display for ShapeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Rock::Horizontal => vec![(0, 0), (1, 0), (2, 0), (3, 0)],
-            Rock::Cross => vec![(1, 0), (0, 1), (1, 1), (2, 1), (1, 2)],
-            Rock::Corner => vec![(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)],
-            Rock::Vertical => vec![(0, 0), (0, 1), (0, 2), (0, 3)],
-            Rock::Square => vec![(0, 0), (1, 0), (0, 1), (1, 1)],
+            ShapeError::Empty => write!(f, "rock shape has no cells"),
+            ShapeError::Cell(c) => write!(f, "unexpected cell '{}' in rock shape", c),
         }
     }
+}
 
-    fn height(&self) -> usize {
+impl Error for ShapeError {}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RulesError {
+    NoRocks,
+    /// Rows are kept as bitmasks, so the chamber is between 1 and 64 wide.
+    Width(usize),
+    /// The rock with this index does not fit between the spawn gap and the right wall.
+    Spawn(usize),
+}
+
+impl Display for RulesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Rock::Horizontal => 1,
-            Rock::Cross => 3,
-            Rock::Corner => 3,
-            Rock::Vertical => 4,
-            Rock::Square => 2,
+            RulesError::NoRocks => write!(f, "no rocks to drop"),
+            RulesError::Width(width) => {
+                write!(f, "chamber is {} wide, it has to be 1 to 64 wide", width)
+            }
+            RulesError::Spawn(rock) => write!(f, "rock {} does not fit where it spawns", rock),
         }
     }
 }
 
-impl Iterator for Rock {
-    type Item = Self;
+impl Error for RulesError {}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        match self {
-            Rock::Horizontal => Some(Rock::Cross),
-            Rock::Cross => Some(Rock::Corner),
-            Rock::Corner => Some(Rock::Vertical),
-            Rock::Vertical => Some(Rock::Square),
-            Rock::Square => Some(Rock::Horizontal),
+/// Cells of a rock relative to its bottom left corner.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rock {
+    cells: Vec<(usize, usize)>,
+    /// Each row as a bitmask with the lowest bit on the left, bottom row first.
+    rows: Vec<u64>,
+    width: usize,
+    height: usize,
+}
+
+const STOCK_ROCKS: &str = "####
+
+.#.
+###
+.#.
+
+..#
+..#
+###
+
+#
+#
+#
+#
+
+##
+##";
+
+impl Rock {
+    /// Reads a shape drawn with `#` for rock and `.` for air, top row first like in the puzzle.
+    pub fn parse(art: &str) -> Result<Self, ShapeError> {
+        let rows: Vec<&str> = art.lines().collect();
+        let mut cells = Vec::new();
+
+        for (i, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                match c {
+                    '#' => cells.push((x, rows.len() - 1 - i)),
+                    '.' => {}
+                    c => return Err(ShapeError::Cell(c)),
+                }
+            }
+        }
+
+        let min_x = cells
+            .iter()
+            .map(|(x, _)| *x)
+            .min()
+            .ok_or(ShapeError::Empty)?;
+        let min_y = cells.iter().map(|(_, y)| *y).min().unwrap();
+        cells
+            .iter_mut()
+            .for_each(|(x, y)| (*x, *y) = (*x - min_x, *y - min_y));
+
+        let width = cells.iter().map(|(x, _)| x + 1).max().unwrap();
+        let height = cells.iter().map(|(_, y)| y + 1).max().unwrap();
+        let mut rows = vec![0; height];
+        cells.iter().for_each(|(x, y)| rows[*y] |= 1 << x);
+
+        Ok(Rock {
+            cells,
+            rows,
+            width,
+            height,
+        })
+    }
+
+    /// Several shapes separated by blank lines.
+    pub fn parse_all(art: &str) -> Result<Vec<Self>, ShapeError> {
+        art.split("\n\n").map(Rock::parse).collect()
+    }
+
+    /// The five rocks of the puzzle, in the order they fall.
+    pub fn stock() -> Vec<Self> {
+        Rock::parse_all(STOCK_ROCKS).unwrap()
+    }
+
+    pub fn get_cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+
+    pub fn get_rows(&self) -> &[u64] {
+        &self.rows
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+}
+
+/// How the chamber is laid out and which rocks fall into it.
+#[derive(Clone, Debug)]
+pub struct Rules {
+    width: usize,
+    /// Gap between a new rock and the left wall.
+    spawn_left: usize,
+    /// Gap between a new rock and the highest rock, or the floor.
+    spawn_above: usize,
+    rocks: Vec<Rock>,
+}
+
+impl Rules {
+    pub fn new(
+        width: usize,
+        spawn_left: usize,
+        spawn_above: usize,
+        rocks: Vec<Rock>,
+    ) -> Result<Self, RulesError> {
+        if rocks.is_empty() {
+            return Err(RulesError::NoRocks);
         }
+
+        if !(1..=64).contains(&width) {
+            return Err(RulesError::Width(width));
+        }
+
+        if let Some(rock) = rocks
+            .iter()
+            .position(|rock| spawn_left + rock.get_width() > width)
+        {
+            return Err(RulesError::Spawn(rock));
+        }
+
+        Ok(Rules {
+            width,
+            spawn_left,
+            spawn_above,
+            rocks,
+        })
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_spawn_left(&self) -> usize {
+        self.spawn_left
+    }
+
+    pub fn get_spawn_above(&self) -> usize {
+        self.spawn_above
+    }
+
+    pub fn get_rocks(&self) -> &[Rock] {
+        &self.rocks
     }
 }
 
-#[derive(PartialEq, Debug)]
+impl Default for Rules {
+    fn default() -> Self {
+        Self::new(7, 2, 3, Rock::stock()).unwrap()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Wind {
     Left,
     Right,
@@ -59,32 +214,15 @@ impl Wind {
             _ => unreachable!(),
         }
     }
-
-    fn blow(&self, rock_coordinates: &mut Vec<(usize, usize)>) {
-        if rock_coordinates.iter().any(|(x, _)| *x == 0) && *self == Self::Left {
-            return;
-        }
-
-        if rock_coordinates.iter().any(|(x, _)| *x == 6) && *self == Self::Right {
-            return;
-        }
-
-        rock_coordinates.iter_mut().for_each(|(x, _)| {
-            *x = match *self {
-                Wind::Left => *x - 1,
-                Wind::Right => *x + 1,
-            };
-        })
-    }
 }
 
 pub struct InfiniteStorm<'a> {
-    winds: &'a Vec<Wind>,
+    winds: &'a [Wind],
     next_index: usize,
 }
 
 impl<'a> InfiniteStorm<'a> {
-    pub fn new(winds: &'a Vec<Wind>) -> Self {
+    pub fn new(winds: &'a [Wind]) -> Self {
         InfiniteStorm {
             winds,
             next_index: 0,
@@ -103,42 +241,59 @@ impl<'a> Iterator for InfiniteStorm<'a> {
             }
             None => {
                 self.next_index = 1;
-                self.winds.get(0)
+                self.winds.first()
             }
         }
     }
 }
 
+/// A rock on its way down, by its index in the rock set and its bottom left corner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Falling {
+    pub rock: usize,
+    pub x: usize,
+    pub y: usize,
+}
+
+/// Rows are bitmasks with the lowest bit at the left wall. Only the rows rocks can still get to
+/// are kept, everything below them is dropped as the tower grows.
 pub struct Chamber<'a> {
-    chamber: Vec<[u8; 7]>,
+    rows: Vec<u64>,
+    /// How many rows at the bottom have been dropped.
+    base: usize,
+    prune_at: usize,
     top: usize,
+    /// The highest a rock has been, counting the space it spawns in.
+    spawned: usize,
     storm: InfiniteStorm<'a>,
+    rules: Rules,
+    next_rock: usize,
 }
 
+/// Every row up to where the last rock spawned, including the empty ones above the tower.
 impl<'a> Display for Chamber<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for row in self.chamber.iter().rev() {
-            write!(f, "|")?;
-            for cell in row.iter() {
-                match cell {
-                    0 => write!(f, ".")?,
-                    1 => write!(f, "#")?,
-                    _ => unreachable!(),
-                };
-            }
-            writeln!(f, "|")?;
-        }
-
-        write!(f, "+-------+")
+        write!(f, "{}", self.draw(self.spawned.max(self.top), &[]))
     }
 }
 
+const PRUNE_AT: usize = 256;
+
 impl<'a> Chamber<'a> {
     pub fn new(storm: InfiniteStorm<'a>) -> Self {
+        Self::with_rules(storm, Rules::default())
+    }
+
+    pub fn with_rules(storm: InfiniteStorm<'a>, rules: Rules) -> Self {
         Chamber {
-            chamber: Vec::new(),
+            rows: Vec::new(),
+            base: 0,
+            prune_at: PRUNE_AT,
             top: 0,
+            spawned: 0,
             storm,
+            rules,
+            next_rock: 0,
         }
     }
 
@@ -146,117 +301,251 @@ impl<'a> Chamber<'a> {
         self.top
     }
 
-    fn spawn_rock(&mut self, rock: &Rock) -> Vec<(usize, usize)> {
-        let bottom_left = (2, self.top + 3);
+    pub fn get_rules(&self) -> &Rules {
+        &self.rules
+    }
 
-        let rock_coords = rock
-            .coords()
-            .into_iter()
-            .map(|(x, y)| (bottom_left.0 + x, bottom_left.1 + y))
-            .collect();
+    /// Rows above the top are empty, rows that were dropped count as full.
+    fn row(&self, y: usize) -> u64 {
+        match y.checked_sub(self.base) {
+            Some(i) => self.rows.get(i).copied().unwrap_or(0),
+            None => self.full(),
+        }
+    }
 
-        let new_top = bottom_left.1 + rock.height() - 1;
-        if new_top >= self.chamber.len() {
-            for _ in self.chamber.len()..=new_top {
-                self.chamber.push([0; 7]);
-            }
+    fn full(&self) -> u64 {
+        u64::MAX >> (64 - self.rules.width)
+    }
+
+    fn spawn_rock(&mut self, rock: usize) -> Falling {
+        let y = self.top + self.rules.spawn_above;
+        self.spawned = self.spawned.max(y + self.rules.rocks[rock].get_height());
+
+        Falling {
+            rock,
+            x: self.rules.spawn_left,
+            y,
+        }
+    }
+
+    fn is_blocked(&self, falling: &Falling) -> bool {
+        let rock = &self.rules.rocks[falling.rock];
+        if falling.x + rock.get_width() > self.rules.width {
+            return true;
         }
 
-        rock_coords
+        rock.get_rows()
+            .iter()
+            .enumerate()
+            .any(|(i, row)| self.row(falling.y + i) & row << falling.x != 0)
     }
 
-    fn is_blocked(&self, rock_coords: &Vec<(usize, usize)>) -> bool {
-        rock_coords.iter().any(|(x, y)| self.chamber[*y][*x] == 1)
+    fn settle(&mut self, falling: &Falling) {
+        let rock = &self.rules.rocks[falling.rock];
+        let top = falling.y + rock.get_height();
+        if top - self.base > self.rows.len() {
+            self.rows.resize(top - self.base, 0);
+        }
+
+        for (i, row) in rock.get_rows().iter().enumerate() {
+            self.rows[falling.y + i - self.base] |= row << falling.x;
+        }
+        self.top = self.top.max(top);
+
+        if self.rows.len() >= self.prune_at {
+            self.prune();
+        }
     }
 
-    fn settle(&mut self, rock_coords: Vec<(usize, usize)>) {
-        rock_coords.into_iter().for_each(|(x, y)| {
-            self.chamber[y][x] = 1;
-            self.top = self.top.max(y + 1);
-        });
+    /// Drops every row below the deepest one a rock could still get into.
+    fn prune(&mut self) {
+        let reach = self.surface();
+        let floor = self.top - reach.len();
+        let drop = floor.saturating_sub(1).saturating_sub(self.base);
+
+        self.rows.drain(..drop);
+        self.base += drop;
+        self.prune_at = PRUNE_AT.max(self.rows.len() * 2);
     }
 
-    pub fn get_top(&self) -> [u8; 7] {
-        self.chamber.get(self.top - 1).unwrap().clone()
+    /// The cells a falling rock could still get to, as a bitmask for each row from the top row
+    /// down to the deepest one it reaches. Everything else is buried for good, so this is all
+    /// that decides how the tower grows.
+    pub fn surface(&self) -> Vec<u64> {
+        let mut surface = Vec::new();
+        let mut reach = self.full();
+
+        for y in (0..self.top).rev() {
+            let free = !self.row(y) & self.full();
+            reach &= free;
+            loop {
+                let spread = (reach | reach << 1 | reach >> 1) & free;
+                if spread == reach {
+                    break;
+                }
+                reach = spread;
+            }
+
+            if reach == 0 {
+                break;
+            }
+            surface.push(reach);
+        }
+
+        surface
     }
 
     pub fn get_wind_index(&self) -> usize {
         self.storm.next_index
     }
 
-    pub fn drop_rock(&mut self, rock: &Rock) {
-        let mut last_rock_coords = self.spawn_rock(rock);
-        let mut wind;
+    /// The index of the next rock to fall in the rock set.
+    pub fn get_rock_index(&self) -> usize {
+        self.next_rock
+    }
 
-        loop {
-            let mut new_rock_coords = last_rock_coords.clone();
+    /// The cells a falling rock takes up.
+    pub fn get_cells(&self, falling: &Falling) -> Vec<(usize, usize)> {
+        self.rules.rocks[falling.rock]
+            .get_cells()
+            .iter()
+            .map(|(x, y)| (falling.x + x, falling.y + y))
+            .collect()
+    }
+
+    pub fn drop_rock(&mut self) {
+        self.drop_rock_observed(&mut ());
+    }
+
+    /// Drops the next rock, showing the observer every move it makes on the way down.
+    pub fn drop_rock_observed<O: Observer + ?Sized>(&mut self, observer: &mut O) {
+        let mut falling = self.spawn_rock(self.next_rock);
+        self.next_rock = (self.next_rock + 1) % self.rules.rocks.len();
+        observer.observe(self, &Event::Spawn(falling));
 
-            wind = self.storm.next().unwrap();
-            wind.blow(&mut new_rock_coords);
-            if self.is_blocked(&new_rock_coords) {
-                new_rock_coords = last_rock_coords.clone();
+        loop {
+            let wind = *self.storm.next().unwrap();
+            let pushed = match wind {
+                Wind::Left => falling.x.checked_sub(1),
+                Wind::Right => Some(falling.x + 1),
             }
+            .map(|x| Falling { x, ..falling })
+            .filter(|pushed| !self.is_blocked(pushed));
 
-            if new_rock_coords.iter().any(|(_, y)| *y == 0) {
-                self.settle(new_rock_coords);
+            if let Some(pushed) = pushed {
+                falling = pushed;
+            }
+            observer.observe(
+                self,
+                &Event::Push {
+                    wind,
+                    moved: pushed.is_some(),
+                    rock: falling,
+                },
+            );
+
+            let fallen = Falling {
+                y: falling.y.wrapping_sub(1),
+                ..falling
+            };
+            if falling.y == 0 || self.is_blocked(&fallen) {
+                self.settle(&falling);
+                observer.observe(self, &Event::Settle(falling));
                 return;
             }
 
-            last_rock_coords = new_rock_coords.clone();
+            falling = fallen;
+            observer.observe(self, &Event::Fall(falling));
+        }
+    }
 
-            new_rock_coords.iter_mut().for_each(|(_, y)| *y -= 1);
-            if self.is_blocked(&new_rock_coords) {
-                self.settle(last_rock_coords);
-                return;
-            }
+    /// The chamber from its highest rock down, with a falling rock drawn as `@` like in the puzzle.
+    /// Rows that were dropped are left out.
+    pub fn render(&self, falling: Option<&Falling>) -> String {
+        let cells = falling
+            .map(|falling| self.get_cells(falling))
+            .unwrap_or_default();
+        let height = cells
+            .iter()
+            .map(|(_, y)| y + 1)
+            .max()
+            .unwrap_or(0)
+            .max(self.top);
+
+        self.draw(height, &cells)
+    }
+
+    fn draw(&self, height: usize, cells: &[(usize, usize)]) -> String {
+        let mut lines: Vec<String> = (self.base..height)
+            .rev()
+            .map(|y| {
+                let row: String = (0..self.rules.width)
+                    .map(|x| match self.row(y) & 1 << x != 0 {
+                        true => '#',
+                        false if cells.contains(&(x, y)) => '@',
+                        false => '.',
+                    })
+                    .collect();
+                format!("|{}|", row)
+            })
+            .collect();
 
-            last_rock_coords = new_rock_coords;
+        match self.base {
+            0 => lines.push(format!("+{}+", "-".repeat(self.rules.width))),
+            _ => lines.push(format!("|{}|", "~".repeat(self.rules.width))),
         }
+
+        lines.join("\n")
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::super::tests::EXAMPLE;
     use super::*;
 
+    fn winds(input: &str) -> Vec<Wind> {
+        input.chars().map(Wind::new).collect()
+    }
+
     #[test]
     fn can_spawn_rock() {
         let winds = Vec::new();
         let mut chamber = Chamber::new(InfiniteStorm::new(&winds));
-        chamber.spawn_rock(&Rock::Cross);
-        assert_eq!(chamber.chamber.len(), 6);
-        assert_eq!(chamber.top, 0);
-        chamber.spawn_rock(&Rock::Horizontal);
-        assert_eq!(chamber.chamber.len(), 6);
-        assert_eq!(chamber.top, 0);
+        assert_eq!(
+            chamber.spawn_rock(1),
+            Falling {
+                rock: 1,
+                x: 2,
+                y: 3
+            }
+        );
+        assert!(chamber.rows.is_empty());
 
         chamber.top = 8;
-        chamber.spawn_rock(&Rock::Horizontal);
-        assert_eq!(chamber.chamber.len(), 12);
+        assert_eq!(chamber.spawn_rock(0).y, 11);
         assert_eq!(chamber.top, 8);
     }
 
     #[test]
-    fn can_iterate_rocks() {
-        let mut rock = Rock::Horizontal;
-        assert_eq!(rock, Rock::Horizontal);
-        rock = rock.next().unwrap();
-        assert_eq!(rock, Rock::Cross);
-        rock = rock.next().unwrap();
-        assert_eq!(rock, Rock::Corner);
-        rock = rock.next().unwrap();
-        assert_eq!(rock, Rock::Vertical);
-        rock = rock.next().unwrap();
-        assert_eq!(rock, Rock::Square);
-        rock = rock.next().unwrap();
-        assert_eq!(rock, Rock::Horizontal);
-        rock = rock.next().unwrap();
-        assert_eq!(rock, Rock::Cross);
+    fn can_parse_rocks() {
+        let rocks = Rock::stock();
+        assert_eq!(rocks.len(), 5);
+        assert_eq!(rocks[0].get_cells(), &[(0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert_eq!(
+            rocks[2].get_cells(),
+            &[(2, 2), (2, 1), (0, 0), (1, 0), (2, 0)]
+        );
+        assert_eq!((rocks[3].get_width(), rocks[3].get_height()), (1, 4));
+
+        assert_eq!(Rock::parse("..\n.#"), Rock::parse("#"));
+        assert_eq!(Rock::parse(".."), Err(ShapeError::Empty));
+        assert_eq!(Rock::parse("#o"), Err(ShapeError::Cell('o')));
     }
 
     #[test]
     fn can_iterate_winds() {
-        let winds = vec!['>', '>', '<', '>', '<']
+        let winds: Vec<Wind> = vec!['>', '>', '<', '>', '<']
             .into_iter()
             .map(|c| Wind::new(c))
             .collect();
@@ -271,4 +560,79 @@ mod tests {
         assert_eq!(storm.next(), Some(&Wind::Right));
         assert_eq!(storm.next(), Some(&Wind::Left));
     }
+
+    #[test]
+    fn can_stack_example() {
+        let winds = winds(EXAMPLE);
+        let mut chamber = Chamber::new(InfiniteStorm::new(&winds));
+        (0..2022).for_each(|_| chamber.drop_rock());
+        assert_eq!(chamber.get_height(), 3068);
+    }
+
+    #[test]
+    fn can_use_custom_rules() {
+        let rocks = Rock::parse_all("###\n\n#\n#").unwrap();
+        let winds = winds("<");
+        let mut chamber = Chamber::with_rules(
+            InfiniteStorm::new(&winds),
+            Rules::new(4, 1, 2, rocks).unwrap(),
+        );
+
+        (0..4).for_each(|_| chamber.drop_rock());
+        assert_eq!(
+            chamber.to_string(),
+            "|....|
+|....|
+|#...|
+|#...|
+|###.|
+|#...|
+|#...|
+|###.|
++----+"
+        );
+    }
+
+    #[test]
+    fn can_prune_rows() {
+        let winds = winds(EXAMPLE);
+        let mut chamber = Chamber::new(InfiniteStorm::new(&winds));
+        (0..10000).for_each(|_| chamber.drop_rock());
+
+        assert!(chamber.base > 0);
+        assert!(chamber.rows.len() < PRUNE_AT);
+        assert!(chamber.render(None).ends_with("|~~~~~~~|"));
+    }
+
+    #[test]
+    fn can_reject_rules() {
+        let rocks = Rock::stock();
+        assert_eq!(
+            Rules::new(7, 2, 3, Vec::new()).err(),
+            Some(RulesError::NoRocks)
+        );
+        assert_eq!(
+            Rules::new(65, 2, 3, rocks.clone()).err(),
+            Some(RulesError::Width(65))
+        );
+        assert_eq!(
+            Rules::new(5, 2, 3, rocks.clone()).err(),
+            Some(RulesError::Spawn(0))
+        );
+        assert!(Rules::new(64, 60, 3, rocks).is_ok());
+    }
+
+    #[test]
+    fn can_find_surface() {
+        let rocks = Rock::parse_all("#.#\n###").unwrap();
+        let winds = winds("<");
+        let mut chamber = Chamber::with_rules(
+            InfiniteStorm::new(&winds),
+            Rules::new(4, 0, 1, rocks).unwrap(),
+        );
+        assert!(chamber.surface().is_empty());
+
+        chamber.drop_rock();
+        assert_eq!(chamber.surface(), vec![0b1010, 0b1000]);
+    }
 }
@@ -1,20 +1,23 @@
-mod cube;
+pub mod cube;
+pub mod mesh;
+pub mod voxels;
 
-use self::cube::{Bounds, Cube, CubeGenerator, Side};
-use std::collections::{HashSet, LinkedList};
+use self::cube::{Cube, Side};
+use self::voxels::{Droplet, GridError};
+use std::collections::HashSet;
 
 #[aoc_generator(day18)]
 fn input_generator(input: &str) -> Vec<Cube> {
     input
         .lines()
         .map(|line| {
-            let parts: Vec<u8> = line.split(',').map(|part| part.parse().unwrap()).collect();
+            let parts: Vec<i32> = line.split(',').map(|part| part.parse().unwrap()).collect();
             Cube::new(parts[0], parts[1], parts[2])
         })
         .collect()
 }
 
-fn get_uncovered_sides(cubes: &Vec<Cube>) -> HashSet<Side> {
+fn get_uncovered_sides(cubes: &[Cube]) -> HashSet<Side> {
     let mut uncovered = HashSet::new();
 
     cubes.iter().for_each(|cube| {
@@ -32,93 +35,42 @@ fn get_uncovered_sides(cubes: &Vec<Cube>) -> HashSet<Side> {
 }
 
 #[aoc(day18, part1)]
-fn count_all(cubes: &Vec<Cube>) -> usize {
+fn count_all(cubes: &[Cube]) -> usize {
     get_uncovered_sides(cubes).len()
 }
 
-fn fill_cubes(
-    start: Cube,
-    visited: &mut HashSet<Cube>,
-    all_uncovered: &HashSet<Side>,
-    inner_uncovered: &mut HashSet<Side>,
-    bounds: &Bounds,
-) {
-    if visited.contains(&start) {
-        return;
-    }
-
-    visited.insert(start);
-    let mut queue = LinkedList::from([start]);
-    let mut sides = HashSet::new();
-    let mut inner = true;
-
-    while let Some(current) = queue.pop_front() {
-        sides.extend(
-            current
-                .sides()
-                .into_iter()
-                .filter(|side| all_uncovered.contains(side)),
-        );
-
-        let neighbors: Vec<Cube> = current
-            .neighbors(all_uncovered)
-            .into_iter()
-            .filter(|cube| !visited.contains(cube))
-            .collect();
-
-        let all_neighbor_count = neighbors.len();
-
-        let neighbors: Vec<Cube> = neighbors
-            .into_iter()
-            .filter(|cube| cube.in_bounds(bounds))
-            .collect();
-
-        if neighbors.len() < all_neighbor_count {
-            inner = false;
-        }
-
-        for cube in neighbors {
-            visited.insert(cube);
-            queue.push_back(cube);
-        }
-    }
-
-    if inner {
-        inner_uncovered.extend(sides.into_iter());
-    }
-}
-
 #[aoc(day18, part2)]
-fn count_outside(cubes: &Vec<Cube>) -> usize {
-    let all_uncovered = get_uncovered_sides(cubes);
-    let bounds = cubes.iter().fold(
-        ((255, 0), (255, 0), (255, 0)),
-        |((x_min, x_max), (y_min, y_max), (z_min, z_max)), cube| {
-            let (x, y, z) = cube.coords();
-            (
-                (x_min.min(x), x_max.max(x)),
-                (y_min.min(y), y_max.max(y)),
-                (z_min.min(z), z_max.max(z)),
-            )
-        },
-    );
-
-    let mut visited_cubes = HashSet::new();
-    for cube in cubes {
-        visited_cubes.insert(*cube);
-    }
+fn count_outside(cubes: &[Cube]) -> Result<usize, GridError> {
+    Ok(Droplet::new(cubes)?.exterior_area())
+}
 
-    let mut inner_sides = HashSet::new();
-    let mut generator = CubeGenerator::new(bounds);
-    while let Some(cube) = generator.next() {
-        fill_cubes(
-            cube,
-            &mut visited_cubes,
-            &all_uncovered,
-            &mut inner_sides,
-            &bounds,
-        );
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(super) const EXAMPLE: &str = "2,2,2
+1,2,2
+3,2,2
+2,1,2
+2,3,2
+2,2,1
+2,2,3
+2,2,4
+2,2,6
+1,2,5
+3,2,5
+2,1,5
+2,3,5";
+
+    #[test]
+    fn can_count_sides() {
+        let cubes = input_generator(EXAMPLE);
+        assert_eq!(count_all(&cubes), 64);
+        assert_eq!(count_outside(&cubes), Ok(58));
+
+        let droplet = Droplet::new(&cubes).unwrap();
+        assert_eq!(droplet.surface_area(), 64);
+        assert_eq!(droplet.get_pockets().len(), 1);
+        assert_eq!(droplet.get_pockets()[0].get_volume(), 1);
     }
-
-    all_uncovered.len() - inner_sides.len()
 }
@@ -1,4 +1,4 @@
-use std::{collections::HashSet, hash::Hash};
+use std::hash::Hash;
 
 #[derive(PartialEq, Hash, Eq, Debug)]
 pub enum SidePlane {
@@ -7,22 +7,22 @@ pub enum SidePlane {
     YZ,
 }
 
-pub type Side = (u8, u8, u8, SidePlane);
-pub type Bounds = ((u8, u8), (u8, u8), (u8, u8));
+pub type Side = (i32, i32, i32, SidePlane);
+pub type Bounds = ((i32, i32), (i32, i32), (i32, i32));
 
 #[derive(PartialEq, Hash, Eq, Copy, Clone, Debug)]
 pub struct Cube {
-    x: u8,
-    y: u8,
-    z: u8,
+    x: i32,
+    y: i32,
+    z: i32,
 }
 
 impl Cube {
-    pub fn new(x: u8, y: u8, z: u8) -> Self {
+    pub fn new(x: i32, y: i32, z: i32) -> Self {
         Self { x, y, z }
     }
 
-    pub fn coords(&self) -> (u8, u8, u8) {
+    pub fn coords(&self) -> (i32, i32, i32) {
         (self.x, self.y, self.z)
     }
 
@@ -46,23 +46,18 @@ impl Cube {
             && self.z <= (bounds.2).1
     }
 
-    pub fn neighbors(&self, uncovered_sides: &HashSet<Side>) -> Vec<Self> {
+    /// The cubes sharing each side, in the same order as `sides()`. Wraps around at the ends of
+    /// the coordinate range, where the neighbour is far off any grid.
+    pub fn adjacent(&self) -> [Self; 6] {
         let (x, y, z) = (self.x, self.y, self.z);
-        let sides = self.sides();
-        // order must be same as sides()
-        vec![
-            Cube::new(x, y, z - 1), // XY
-            Cube::new(x, y - 1, z), // XZ
-            Cube::new(x - 1, y, z), // YZ
-            Cube::new(x, y, z + 1), // XY
-            Cube::new(x, y + 1, z), // XZ
-            Cube::new(x + 1, y, z), // YZ
+        [
+            Cube::new(x, y, z.wrapping_sub(1)),
+            Cube::new(x, y.wrapping_sub(1), z),
+            Cube::new(x.wrapping_sub(1), y, z),
+            Cube::new(x, y, z.wrapping_add(1)),
+            Cube::new(x, y.wrapping_add(1), z),
+            Cube::new(x.wrapping_add(1), y, z),
         ]
-        .into_iter()
-        .enumerate()
-        .filter(|(i, _)| !uncovered_sides.contains(&sides[*i]))
-        .map(|(_, cube)| cube)
-        .collect()
     }
 }
 
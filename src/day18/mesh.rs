@@ -0,0 +1,262 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::{self, Write},
+};
+
+use super::voxels::{Droplet, Voxel};
+
+type Vertex = (i32, i32, i32);
+
+/// A rectangle of the outer surface, with its corners counter-clockwise seen from outside.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Quad {
+    corners: [Vertex; 4],
+    normal: Vertex,
+}
+
+impl Quad {
+    /// The face of a plane `layer` along `axis`, spanning `u` and `v` along the next two axes.
+    fn new(axis: usize, outwards: bool, layer: i32, u: (i32, i32), v: (i32, i32)) -> Self {
+        let point = |u, v| {
+            let mut point = [0; 3];
+            point[axis] = layer;
+            point[(axis + 1) % 3] = u;
+            point[(axis + 2) % 3] = v;
+            (point[0], point[1], point[2])
+        };
+
+        let mut corners = [
+            point(u.0, v.0),
+            point(u.1, v.0),
+            point(u.1, v.1),
+            point(u.0, v.1),
+        ];
+        if !outwards {
+            corners.reverse();
+        }
+
+        let mut normal = [0; 3];
+        normal[axis] = if outwards { 1 } else { -1 };
+
+        Quad {
+            corners,
+            normal: (normal[0], normal[1], normal[2]),
+        }
+    }
+
+    pub fn get_corners(&self) -> &[Vertex; 4] {
+        &self.corners
+    }
+
+    pub fn get_normal(&self) -> Vertex {
+        self.normal
+    }
+
+    pub fn get_area(&self) -> usize {
+        let [(x0, y0, z0), _, (x1, y1, z1), _] = self.corners;
+        [x1 - x0, y1 - y0, z1 - z0]
+            .iter()
+            .filter(|side| **side != 0)
+            .map(|side| side.unsigned_abs() as usize)
+            .product()
+    }
+}
+
+/// The sides of lava that steam can get to, as a surface for 3D tools.
+pub struct Mesh {
+    quads: Vec<Quad>,
+}
+
+impl Mesh {
+    /// One square per side, or with `merge` as few rectangles as the greedy approach finds for
+    /// each plane. Merged meshes can have vertices in the middle of a neighbour's edge, which
+    /// viewers cope with but some slicers do not.
+    pub fn new(droplet: &Droplet, merge: bool) -> Self {
+        // sides by the axis they face along, whether they face its positive end and their plane
+        let mut planes: HashMap<(usize, bool, i32), BTreeSet<(i32, i32)>> = HashMap::new();
+
+        for cube in droplet.lava() {
+            let (x, y, z) = cube.coords();
+            let coords = [x, y, z];
+
+            for (i, next) in cube.adjacent().iter().enumerate() {
+                if droplet.get(next) != Voxel::Outside {
+                    continue;
+                }
+
+                // same order as `Cube::adjacent`
+                let axis = 2 - i % 3;
+                let outwards = i >= 3;
+                let layer = coords[axis] + outwards as i32;
+                planes
+                    .entry((axis, outwards, layer))
+                    .or_default()
+                    .insert((coords[(axis + 1) % 3], coords[(axis + 2) % 3]));
+            }
+        }
+
+        let mut planes: Vec<_> = planes.into_iter().collect();
+        planes.sort_unstable();
+
+        let quads = planes
+            .into_iter()
+            .flat_map(|((axis, outwards, layer), cells)| {
+                let rectangles = match merge {
+                    true => merge_cells(cells),
+                    false => cells
+                        .into_iter()
+                        .map(|(u, v)| ((u, u + 1), (v, v + 1)))
+                        .collect(),
+                };
+
+                rectangles
+                    .into_iter()
+                    .map(move |(u, v)| Quad::new(axis, outwards, layer, u, v))
+            })
+            .collect();
+
+        Mesh { quads }
+    }
+
+    pub fn get_quads(&self) -> &[Quad] {
+        &self.quads
+    }
+
+    /// Wavefront OBJ with shared vertices and a quad per face.
+    pub fn write_obj(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut vertices: HashMap<Vertex, usize> = HashMap::new();
+        let mut faces = Vec::with_capacity(self.quads.len());
+
+        writeln!(out, "o droplet")?;
+        for quad in self.quads.iter() {
+            let mut face = Vec::with_capacity(4);
+            for corner in quad.corners {
+                let next = vertices.len() + 1;
+                let index = *vertices.entry(corner).or_insert(next);
+                if index == next {
+                    writeln!(out, "v {} {} {}", corner.0, corner.1, corner.2)?;
+                }
+                face.push(index.to_string());
+            }
+            faces.push(face.join(" "));
+        }
+
+        for face in faces {
+            writeln!(out, "f {}", face)?;
+        }
+
+        Ok(())
+    }
+
+    /// ASCII STL, with each quad split into two triangles.
+    pub fn write_stl(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "solid droplet")?;
+        for quad in self.quads.iter() {
+            let [a, b, c, d] = quad.corners;
+            for triangle in [[a, b, c], [a, c, d]] {
+                let (x, y, z) = quad.normal;
+                writeln!(out, "facet normal {} {} {}", x, y, z)?;
+                writeln!(out, "  outer loop")?;
+                for (x, y, z) in triangle {
+                    writeln!(out, "    vertex {} {} {}", x, y, z)?;
+                }
+                writeln!(out, "  endloop")?;
+                writeln!(out, "endfacet")?;
+            }
+        }
+
+        writeln!(out, "endsolid droplet")
+    }
+}
+
+/// Covers the cells of a plane with rectangles, each grown as far as it goes along `u` first
+/// and then along `v`. Rectangles are given as half-open ranges.
+fn merge_cells(mut cells: BTreeSet<(i32, i32)>) -> Vec<((i32, i32), (i32, i32))> {
+    let mut rectangles = Vec::new();
+
+    while let Some((u, v)) = cells.pop_first() {
+        let mut u_end = u + 1;
+        while cells.remove(&(u_end, v)) {
+            u_end += 1;
+        }
+
+        let mut v_end = v + 1;
+        while (u..u_end).all(|u| cells.contains(&(u, v_end))) {
+            (u..u_end).for_each(|u| {
+                cells.remove(&(u, v_end));
+            });
+            v_end += 1;
+        }
+
+        rectangles.push(((u, u_end), (v, v_end)));
+    }
+
+    rectangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::cube::{Cube, CubeGenerator};
+    use super::super::input_generator;
+    use super::super::tests::EXAMPLE;
+    use super::*;
+
+    fn example() -> Droplet {
+        Droplet::new(&input_generator(EXAMPLE)).unwrap()
+    }
+
+    #[test]
+    fn can_build_closed_surface() {
+        let droplet = example();
+        let mesh = Mesh::new(&droplet, false);
+        assert_eq!(mesh.get_quads().len(), 58);
+
+        // every edge is walked once in each direction when the faces are wound consistently
+        let mut edges: HashMap<(Vertex, Vertex), i32> = HashMap::new();
+        for quad in mesh.get_quads() {
+            let corners = quad.get_corners();
+            for i in 0..4 {
+                let (from, to) = (corners[i], corners[(i + 1) % 4]);
+                *edges.entry((from.min(to), from.max(to))).or_default() +=
+                    if from < to { 1 } else { -1 };
+            }
+        }
+        assert!(edges.values().all(|count| *count == 0));
+    }
+
+    #[test]
+    fn can_merge_faces() {
+        let cubes: Vec<Cube> = CubeGenerator::new(((-1, 1), (0, 1), (5, 6))).collect();
+        let mesh = Mesh::new(&Droplet::new(&cubes).unwrap(), true);
+        assert_eq!(mesh.get_quads().len(), 6);
+        assert!(mesh.get_quads().contains(&Quad {
+            corners: [(2, 0, 5), (2, 2, 5), (2, 2, 7), (2, 0, 7)],
+            normal: (1, 0, 0),
+        }));
+
+        let droplet = example();
+        let merged = Mesh::new(&droplet, true);
+        let area: usize = merged.get_quads().iter().map(Quad::get_area).sum();
+        assert_eq!(area, droplet.exterior_area());
+        assert!(merged.get_quads().len() < 58);
+    }
+
+    #[test]
+    fn can_write_files() {
+        let mesh = Mesh::new(&Droplet::new(&[Cube::new(0, 0, 0)]).unwrap(), false);
+
+        let mut obj = Vec::new();
+        mesh.write_obj(&mut obj).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert_eq!(obj.lines().filter(|line| line.starts_with("v ")).count(), 8);
+        assert_eq!(obj.lines().filter(|line| line.starts_with("f ")).count(), 6);
+        assert!(obj.starts_with("o droplet\nv 0 0 1\nv 0 1 1\nv 0 1 0\nv 0 0 0\n"));
+
+        let mut stl = Vec::new();
+        mesh.write_stl(&mut stl).unwrap();
+        let stl = String::from_utf8(stl).unwrap();
+        assert_eq!(stl.matches("facet normal").count(), 12);
+        assert!(stl.starts_with("solid droplet\nfacet normal -1 0 0\n  outer loop\n"));
+        assert!(stl.ends_with("endsolid droplet\n"));
+    }
+}
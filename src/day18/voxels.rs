@@ -0,0 +1,286 @@
+use std::{error::Error, fmt::Display};
+
+use super::cube::{Bounds, Cube, CubeGenerator};
+
+/// Voxels take 8 bytes each, so this keeps the grid to 128 MB.
+pub const MAX_VOXELS: u64 = 1 << 24;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GridError {
+    /// The layer of air around the droplet would not fit into `i32` coordinates.
+    OutOfRange,
+    /// The bounding box with its layer of air is too big for a dense grid.
+    TooLarge { width: u64, depth: u64, height: u64 },
+}
+
+impl Display for GridError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GridError::OutOfRange => write!(f, "droplet reaches the edge of the coordinate range"),
+            GridError::TooLarge {
+                width,
+                depth,
+                height,
+            } => write!(
+                f,
+                "{}x{}x{} grid is larger than {} voxels",
+                width, depth, height, MAX_VOXELS
+            ),
+        }
+    }
+}
+
+impl Error for GridError {}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Voxel {
+    Lava,
+    /// Air that steam can get to from outside the droplet.
+    Outside,
+    /// Air trapped inside the droplet, by the index of its pocket.
+    Pocket(u32),
+}
+
+/// A bubble of trapped air.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Pocket {
+    volume: usize,
+    area: usize,
+}
+
+impl Pocket {
+    /// How many cubes of air it holds.
+    pub fn get_volume(&self) -> usize {
+        self.volume
+    }
+
+    /// How many sides of lava face into it.
+    pub fn get_area(&self) -> usize {
+        self.area
+    }
+}
+
+/// The droplet as a dense grid over its bounding box with a layer of air all around, so
+/// everything outside it is connected.
+pub struct Droplet {
+    bounds: Bounds,
+    voxels: Vec<Voxel>,
+    pockets: Vec<Pocket>,
+}
+
+impl Droplet {
+    pub fn new(cubes: &[Cube]) -> Result<Self, GridError> {
+        let range = |axis: fn(&Cube) -> i32| {
+            let min = cubes.iter().map(axis).min().unwrap_or(0);
+            let max = cubes.iter().map(axis).max().unwrap_or(0);
+            Some((min.checked_sub(1)?, max.checked_add(1)?))
+        };
+        let bounds = (
+            range(|cube| cube.coords().0).ok_or(GridError::OutOfRange)?,
+            range(|cube| cube.coords().1).ok_or(GridError::OutOfRange)?,
+            range(|cube| cube.coords().2).ok_or(GridError::OutOfRange)?,
+        );
+
+        let len = |(min, max): (i32, i32)| (i64::from(max) - i64::from(min) + 1) as u64;
+        let (width, depth, height) = (len(bounds.0), len(bounds.1), len(bounds.2));
+        let size = width
+            .checked_mul(depth)
+            .and_then(|area| area.checked_mul(height))
+            .filter(|size| *size <= MAX_VOXELS)
+            .ok_or(GridError::TooLarge {
+                width,
+                depth,
+                height,
+            })?;
+
+        let mut droplet = Droplet {
+            bounds,
+            voxels: vec![Voxel::Pocket(u32::MAX); size as usize],
+            pockets: Vec::new(),
+        };
+
+        for cube in cubes {
+            let index = droplet.index(cube).unwrap();
+            droplet.voxels[index] = Voxel::Lava;
+        }
+
+        let corner = Cube::new((bounds.0).0, (bounds.1).0, (bounds.2).0);
+        droplet.fill(corner, Voxel::Outside);
+
+        for cube in CubeGenerator::new(bounds) {
+            if droplet.get(&cube) == Voxel::Pocket(u32::MAX) {
+                // there are fewer pockets than voxels, which fit in a `u32`
+                let pocket = Voxel::Pocket(droplet.pockets.len() as u32);
+                let volume = droplet.fill(cube, pocket);
+                droplet.pockets.push(Pocket { volume, area: 0 });
+            }
+        }
+
+        // the grid rather than the input, which may list a cube twice
+        let mut areas = vec![0; droplet.pockets.len()];
+        for cube in droplet.lava() {
+            for next in cube.adjacent() {
+                if let Voxel::Pocket(pocket) = droplet.get(&next) {
+                    areas[pocket as usize] += 1;
+                }
+            }
+        }
+
+        for (pocket, area) in droplet.pockets.iter_mut().zip(areas) {
+            pocket.area = area;
+        }
+
+        Ok(droplet)
+    }
+
+    fn index(&self, cube: &Cube) -> Option<usize> {
+        if !cube.in_bounds(&self.bounds) {
+            return None;
+        }
+
+        let ((x_min, x_max), (y_min, y_max), (z_min, _)) = self.bounds;
+        let (x, y, z) = cube.coords();
+        let (width, depth) = ((x_max - x_min + 1) as usize, (y_max - y_min + 1) as usize);
+
+        Some(((z - z_min) as usize * depth + (y - y_min) as usize) * width + (x - x_min) as usize)
+    }
+
+    /// Anything past the padded bounding box is outside.
+    pub fn get(&self, cube: &Cube) -> Voxel {
+        self.index(cube)
+            .map_or(Voxel::Outside, |index| self.voxels[index])
+    }
+
+    /// Marks the air connected to `start` that has not been marked yet and returns how much
+    /// there was.
+    fn fill(&mut self, start: Cube, voxel: Voxel) -> usize {
+        let unmarked = self.get(&start);
+        let mut stack = vec![start];
+        let mut count = 0;
+        let index = self.index(&start).unwrap();
+        self.voxels[index] = voxel;
+
+        while let Some(current) = stack.pop() {
+            count += 1;
+            for next in current.adjacent() {
+                let Some(index) = self.index(&next) else {
+                    continue;
+                };
+
+                if self.voxels[index] == unmarked {
+                    self.voxels[index] = voxel;
+                    stack.push(next);
+                }
+            }
+        }
+
+        count
+    }
+
+    pub fn get_bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+
+    pub fn get_pockets(&self) -> &[Pocket] {
+        &self.pockets
+    }
+
+    /// Sides of lava facing air anywhere, including the pockets.
+    pub fn surface_area(&self) -> usize {
+        self.exterior_area() + self.pockets.iter().map(Pocket::get_area).sum::<usize>()
+    }
+
+    /// Sides of lava that steam can get to.
+    pub fn exterior_area(&self) -> usize {
+        self.lava()
+            .flat_map(|cube| cube.adjacent())
+            .filter(|cube| self.get(cube) == Voxel::Outside)
+            .count()
+    }
+
+    pub fn lava(&self) -> impl Iterator<Item = Cube> + '_ {
+        CubeGenerator::new(self.bounds).filter(|cube| self.get(cube) == Voxel::Lava)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A hollow box with its corners at `from` and `to` and walls one cube thick.
+    fn shell(from: (i32, i32, i32), to: (i32, i32, i32)) -> Vec<Cube> {
+        CubeGenerator::new(((from.0, to.0), (from.1, to.1), (from.2, to.2)))
+            .filter(|cube| {
+                let (x, y, z) = cube.coords();
+                [
+                    x == from.0,
+                    x == to.0,
+                    y == from.1,
+                    y == to.1,
+                    z == from.2,
+                    z == to.2,
+                ]
+                .contains(&true)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn can_find_pockets() {
+        let mut cubes = shell((-3, -3, -3), (-1, -1, -1));
+        cubes.extend(shell((300, 0, 5), (304, 3, 8)));
+        let droplet = Droplet::new(&cubes).unwrap();
+
+        assert_eq!(droplet.exterior_area(), 54 + 2 * (5 * 4 + 5 * 4 + 4 * 4));
+        assert_eq!(
+            droplet.get_pockets(),
+            [
+                Pocket { volume: 1, area: 6 },
+                Pocket {
+                    volume: 3 * 2 * 2,
+                    area: 2 * (3 * 2 + 3 * 2 + 2 * 2)
+                }
+            ]
+        );
+        assert_eq!(droplet.get(&Cube::new(-2, -2, -2)), Voxel::Pocket(0));
+        assert_eq!(droplet.get(&Cube::new(0, 0, 0)), Voxel::Outside);
+        assert_eq!(std::mem::size_of::<Voxel>(), 8);
+    }
+
+    #[test]
+    fn can_handle_no_cubes() {
+        let droplet = Droplet::new(&[]).unwrap();
+        assert_eq!(droplet.surface_area(), 0);
+        assert!(droplet.get_pockets().is_empty());
+    }
+
+    #[test]
+    fn can_handle_repeated_cubes() {
+        let mut cubes = shell((0, 0, 0), (2, 2, 2));
+        cubes.extend(shell((0, 0, 0), (2, 2, 2)));
+        let droplet = Droplet::new(&cubes).unwrap();
+
+        assert_eq!(droplet.exterior_area(), 54);
+        assert_eq!(droplet.get_pockets(), [Pocket { volume: 1, area: 6 }]);
+    }
+
+    #[test]
+    fn can_refuse_huge_grids() {
+        let far = [Cube::new(0, 0, 0), Cube::new(100000, 100000, 100000)];
+        assert_eq!(
+            Droplet::new(&far).err(),
+            Some(GridError::TooLarge {
+                width: 100003,
+                depth: 100003,
+                height: 100003
+            })
+        );
+
+        let edges = [Cube::new(i32::MIN, 0, 0), Cube::new(i32::MAX, 0, 0)];
+        assert_eq!(Droplet::new(&edges[..1]).err(), Some(GridError::OutOfRange));
+        assert_eq!(Droplet::new(&edges[1..]).err(), Some(GridError::OutOfRange));
+
+        let near_edge = Droplet::new(&[Cube::new(i32::MIN + 1, i32::MAX - 1, 0)]).unwrap();
+        assert_eq!(near_edge.exterior_area(), 6);
+    }
+}
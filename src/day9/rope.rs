@@ -1,31 +1,87 @@
 use std::{
     collections::HashSet,
+    error::Error,
+    fmt::Display,
+    num::ParseIntError,
     ops::{Add, Sub},
+    slice::Iter,
 };
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    Format(String),
+    Direction(String),
+    Count(ParseIntError),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Format(line) => write!(f, "invalid move: {:?}", line),
+            ParseError::Direction(dir) => write!(f, "invalid direction: {:?}", dir),
+            ParseError::Count(err) => write!(f, "invalid step count: {}", err),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, PartialEq)]
 pub enum Direction {
     Left,
     Right,
     Down,
     Up,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
 }
 
 impl Direction {
-    pub fn parse(input: &str) -> Self {
+    /// Diagonals can be written in either order, `UL` and `LU` are the same move.
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
         match input {
-            "L" => Self::Left,
-            "R" => Self::Right,
-            "D" => Self::Down,
-            "U" => Self::Up,
-            _ => panic!("invalid direction"),
+            "L" => Ok(Self::Left),
+            "R" => Ok(Self::Right),
+            "D" => Ok(Self::Down),
+            "U" => Ok(Self::Up),
+            "UL" | "LU" => Ok(Self::UpLeft),
+            "UR" | "RU" => Ok(Self::UpRight),
+            "DL" | "LD" => Ok(Self::DownLeft),
+            "DR" | "RD" => Ok(Self::DownRight),
+            _ => Err(ParseError::Direction(input.to_string())),
+        }
+    }
+
+    fn delta(&self) -> Point {
+        match self {
+            Direction::Left => Point::new(-1, 0),
+            Direction::Right => Point::new(1, 0),
+            Direction::Down => Point::new(0, -1),
+            Direction::Up => Point::new(0, 1),
+            Direction::UpLeft => Point::new(-1, 1),
+            Direction::UpRight => Point::new(1, 1),
+            Direction::DownLeft => Point::new(-1, -1),
+            Direction::DownRight => Point::new(1, -1),
         }
     }
 }
 
-pub type Move = (Direction, u8);
+pub type Move = (Direction, u32);
+
+pub fn parse_move(line: &str) -> Result<Move, ParseError> {
+    let (direction, count) = line
+        .split_once(' ')
+        .ok_or_else(|| ParseError::Format(line.to_string()))?;
 
-#[derive(Copy, Clone, Hash, Eq, PartialEq)]
+    Ok((
+        Direction::parse(direction)?,
+        count.parse().map_err(ParseError::Count)?,
+    ))
+}
+
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
 pub struct Point {
     x: i32,
     y: i32,
@@ -37,12 +93,7 @@ impl Point {
     }
 
     fn next_in_dir(&self, direction: &Direction) -> Self {
-        match direction {
-            Direction::Left => self + &Point::new(-1, 0),
-            Direction::Right => self + &Point::new(1, 0),
-            Direction::Down => self + &Point::new(0, -1),
-            Direction::Up => self + &Point::new(0, 1),
-        }
+        self + &direction.delta()
     }
 }
 
@@ -68,66 +119,294 @@ impl Sub for &Point {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Follow {
+    /// Step on both axes at once, like the puzzle.
+    Diagonal,
+    /// Step on one axis at a time, the one further away first.
+    Orthogonal,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Physics {
+    slack: i32,
+    follow: Follow,
+}
+
+impl Physics {
+    /// `slack` is how far a knot may lag behind on either axis before it has to catch up.
+    pub fn new(slack: i32, follow: Follow) -> Self {
+        assert!(slack >= 1);
+        Physics { slack, follow }
+    }
+
+    fn is_slack(&self, diff: &Point) -> bool {
+        diff.x.abs() <= self.slack && diff.y.abs() <= self.slack
+    }
+
+    fn step(&self, diff: &Point) -> Point {
+        match self.follow {
+            Follow::Diagonal => Point::new(diff.x.signum(), diff.y.signum()),
+            Follow::Orthogonal if diff.x.abs() >= diff.y.abs() => Point::new(diff.x.signum(), 0),
+            Follow::Orthogonal => Point::new(0, diff.y.signum()),
+        }
+    }
+
+    fn follow(&self, knot: &Point, leader: &Point) -> Point {
+        let mut knot = *knot;
+        let mut diff = leader - &knot;
+        while !self.is_slack(&diff) {
+            knot = &knot + &self.step(&diff);
+            diff = leader - &knot;
+        }
+
+        knot
+    }
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Physics::new(1, Follow::Diagonal)
+    }
+}
+
 pub struct Rope {
     parts: Vec<Point>,
-    tail_history: HashSet<Point>,
+    start: Point,
+    history: Vec<HashSet<Point>>,
+    physics: Physics,
 }
 
 impl Rope {
     pub fn new(parts: Vec<Point>) -> Self {
+        Self::with_physics(parts, Physics::default())
+    }
+
+    pub fn with_physics(parts: Vec<Point>, physics: Physics) -> Self {
         assert!(parts.len() >= 2);
-        let tail_pos = *parts.last().unwrap();
+        let history = parts.iter().map(|part| HashSet::from([*part])).collect();
         Self {
+            start: parts[0],
             parts,
-            tail_history: HashSet::from([tail_pos]),
+            history,
+            physics,
         }
     }
 
-    pub fn get_tail_history_count(&self) -> usize {
-        self.tail_history.len()
+    pub fn get_knots(&self) -> &[Point] {
+        &self.parts
+    }
+
+    pub fn get_history(&self, knot: usize) -> &HashSet<Point> {
+        &self.history[knot]
     }
 
-    fn move_part(&mut self, part: usize, direction: &Direction) -> Option<usize> {
-        let current = self.parts.get(part).unwrap();
-        let next_pos = match self.parts.get(part - 1) {
-            Some(last) => {
-                let mut diff = last - current;
-                if diff.x.abs() <= 1 && diff.y.abs() <= 1 {
-                    return None;
-                }
+    pub fn get_tail_history_count(&self) -> usize {
+        self.history.last().unwrap().len()
+    }
 
-                diff.x = diff.x.checked_div(diff.x.abs()).unwrap_or(0);
-                diff.y = diff.y.checked_div(diff.y.abs()).unwrap_or(0);
+    fn step(&mut self, direction: &Direction) {
+        self.parts[0] = self.parts[0].next_in_dir(direction);
+        self.history[0].insert(self.parts[0]);
 
-                Some(current + &diff)
+        for part in 1..self.parts.len() {
+            let next = self
+                .physics
+                .follow(&self.parts[part], &self.parts[part - 1]);
+            if next == self.parts[part] {
+                break;
             }
-            None => Some(current.next_in_dir(direction)),
-        };
-
-        if let Some(next) = next_pos {
-            let curr = self.parts.get_mut(part).unwrap();
-            *curr = next;
-
-            return if part == self.parts.len() - 1 {
-                self.tail_history.insert(*self.parts.last().unwrap());
-                None
-            } else {
-                Some(part + 1)
-            };
-        }
 
-        None
+            self.parts[part] = next;
+            self.history[part].insert(next);
+        }
     }
 
     pub fn make_move(&mut self, mv: &Move) {
-        let mut i = 0;
-        while i < mv.1 {
-            let mut current = 0;
-            while let Some(next) = self.move_part(current, &mv.0) {
-                current = next
-            }
+        for _ in 0..mv.1 {
+            self.step(&mv.0);
+        }
+    }
 
-            i += 1;
+    /// Applies the moves one step at a time, yielding the knot positions after each step.
+    pub fn frames<'a, 'b>(&'a mut self, moves: &'b [Move]) -> Frames<'a, 'b> {
+        Frames {
+            rope: self,
+            moves: moves.iter(),
+            current: None,
         }
     }
+
+    fn label(&self, knot: usize) -> char {
+        match knot {
+            0 => 'H',
+            k if k == self.parts.len() - 1 && self.parts.len() == 2 => 'T',
+            k => char::from_digit(k as u32, 36).unwrap_or('#'),
+        }
+    }
+
+    fn bounds<'a>(&self, points: impl Iterator<Item = &'a Point>) -> (Point, Point) {
+        points.fold((self.start, self.start), |(min, max), p| {
+            (
+                Point::new(min.x.min(p.x), min.y.min(p.y)),
+                Point::new(max.x.max(p.x), max.y.max(p.y)),
+            )
+        })
+    }
+
+    fn render(&self, (min, max): (Point, Point), cell: impl Fn(&Point) -> Option<char>) -> String {
+        (min.y..=max.y)
+            .rev()
+            .map(|y| {
+                (min.x..=max.x)
+                    .map(|x| {
+                        let point = Point::new(x, y);
+                        cell(&point).unwrap_or(if point == self.start { 's' } else { '.' })
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Draws every position the given knot has visited, like the puzzle's `#` diagrams.
+    pub fn render_history(&self, knot: usize) -> String {
+        let history = &self.history[knot];
+        self.render(self.bounds(history.iter()), |point| {
+            (history.contains(point) && *point != self.start).then_some('#')
+        })
+    }
+}
+
+impl Display for Rope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let out = self.render(self.bounds(self.parts.iter()), |point| {
+            self.parts
+                .iter()
+                .position(|part| part == point)
+                .map(|knot| self.label(knot))
+        });
+
+        write!(f, "{}", out)
+    }
+}
+
+pub struct Frames<'a, 'b> {
+    rope: &'a mut Rope,
+    moves: Iter<'b, Move>,
+    current: Option<(&'b Direction, u32)>,
+}
+
+impl<'a, 'b> Iterator for Frames<'a, 'b> {
+    type Item = Vec<Point>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !matches!(self.current, Some((_, left)) if left > 0) {
+            let (direction, count) = self.moves.next()?;
+            self.current = Some((direction, *count));
+        }
+
+        let (direction, left) = self.current.as_mut().unwrap();
+        *left -= 1;
+        self.rope.step(direction);
+
+        Some(self.rope.parts.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<Move> {
+        vec![
+            (Direction::Right, 4),
+            (Direction::Up, 4),
+            (Direction::Left, 3),
+            (Direction::Down, 1),
+            (Direction::Right, 4),
+            (Direction::Down, 1),
+            (Direction::Left, 5),
+            (Direction::Right, 2),
+        ]
+    }
+
+    #[test]
+    fn can_track_every_knot() {
+        let mut rope = Rope::new(vec![Point::new(0, 0); 10]);
+        example().iter().for_each(|mv| rope.make_move(mv));
+
+        assert_eq!(rope.get_history(1).len(), 13);
+        assert_eq!(rope.get_tail_history_count(), 1);
+    }
+
+    #[test]
+    fn can_iterate_frames() {
+        let moves = example();
+        let mut rope = Rope::new(vec![Point::new(0, 0); 2]);
+        let frames: Vec<Vec<Point>> = rope.frames(&moves).collect();
+
+        assert_eq!(frames.len(), 24);
+        assert_eq!(frames[0], vec![Point::new(1, 0), Point::new(0, 0)]);
+        assert_eq!(frames[4], vec![Point::new(4, 1), Point::new(3, 0)]);
+        assert_eq!(rope.get_tail_history_count(), 13);
+    }
+
+    #[test]
+    fn can_render_rope() {
+        let mut rope = Rope::new(vec![Point::new(0, 0); 2]);
+        rope.make_move(&(Direction::Right, 4));
+        rope.make_move(&(Direction::Up, 2));
+
+        assert_eq!(rope.to_string(), "....H\n....T\ns....");
+        assert_eq!(rope.render_history(1), "....#\ns###.");
+    }
+
+    #[test]
+    fn can_follow_with_slack() {
+        let mut rope =
+            Rope::with_physics(vec![Point::new(0, 0); 2], Physics::new(2, Follow::Diagonal));
+        rope.make_move(&(Direction::Right, 3));
+        assert_eq!(rope.get_knots(), &[Point::new(3, 0), Point::new(1, 0)]);
+    }
+
+    #[test]
+    fn can_parse_moves() {
+        assert_eq!(parse_move("R 4"), Ok((Direction::Right, 4)));
+        assert_eq!(parse_move("UL 300"), Ok((Direction::UpLeft, 300)));
+        assert_eq!(parse_move("RD 1"), Ok((Direction::DownRight, 1)));
+        assert_eq!(
+            parse_move("X 1"),
+            Err(ParseError::Direction("X".to_string()))
+        );
+        assert_eq!(parse_move("R4"), Err(ParseError::Format("R4".to_string())));
+        assert!(matches!(parse_move("R -1"), Err(ParseError::Count(_))));
+    }
+
+    #[test]
+    fn can_follow_diagonal_moves() {
+        let mut rope = Rope::new(vec![Point::new(0, 0); 3]);
+        rope.make_move(&(Direction::UpRight, 3));
+        assert_eq!(
+            rope.get_knots(),
+            &[Point::new(3, 3), Point::new(2, 2), Point::new(1, 1)]
+        );
+
+        rope.make_move(&(Direction::Left, 2));
+        assert_eq!(
+            rope.get_knots(),
+            &[Point::new(1, 3), Point::new(2, 2), Point::new(1, 1)]
+        );
+    }
+
+    #[test]
+    fn can_follow_orthogonally() {
+        let mut rope = Rope::with_physics(
+            vec![Point::new(0, 0); 2],
+            Physics::new(1, Follow::Orthogonal),
+        );
+        rope.make_move(&(Direction::Right, 1));
+        rope.make_move(&(Direction::Up, 2));
+        assert_eq!(rope.get_knots(), &[Point::new(1, 2), Point::new(0, 1)]);
+    }
 }